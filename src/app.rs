@@ -1,10 +1,15 @@
-use crate::components::{AboutPage, ArchivePage, Giscus, PostSummaryCard};
-use crate::posts::{Post, PostSummary};
+use crate::components::{
+  ArchivePage, AuthorPage, Byline, CodeBlockTools, Giscus, Picture, PostSummaryCard, SeriesPage,
+  StandalonePage, TableOfContents,
+};
+use crate::images::CONTENT_SIZES;
+use crate::pages::NavLink;
+use crate::posts::{language, prefer_language, Post, PostSummary, DEFAULT_LANGUAGE, LANGUAGES};
 use leptos::prelude::*;
 use leptos_meta::{provide_meta_context, Meta, MetaTags, Stylesheet, Title};
 use leptos_router::{
   components::{Route, Router, Routes},
-  path, StaticSegment,
+  path, SsrMode, StaticSegment,
 };
 
 // Global search context
@@ -14,6 +19,26 @@ pub struct SearchContext {
   pub current_page: RwSignal<usize>,
 }
 
+// Active content language, chosen in the header and remembered in localStorage
+#[derive(Clone, Copy)]
+pub struct LanguageContext {
+  pub lang: RwSignal<String>,
+}
+
+impl LanguageContext {
+  /// Switch the active language and remember the choice
+  pub fn set(&self, code: &str) {
+    self.lang.set(code.to_string());
+
+    #[cfg(target_arch = "wasm32")]
+    {
+      if let Some(storage) = window().local_storage().ok().flatten() {
+        let _ = storage.set_item("lang", code);
+      }
+    }
+  }
+}
+
 #[server]
 pub async fn get_post_summaries() -> Result<Vec<PostSummary>, ServerFnError> {
   Ok(crate::posts::load_post_summaries())
@@ -32,7 +57,7 @@ pub async fn get_posts_by_tag_summaries(tag: String) -> Result<Vec<PostSummary>,
 
 #[server]
 pub async fn get_posts() -> Result<Vec<Post>, ServerFnError> {
-  Ok(crate::posts::load_posts())
+  Ok(crate::posts::load_listed_posts())
 }
 
 #[server]
@@ -43,7 +68,7 @@ pub async fn get_post_by_slug(slug: String) -> Result<Option<Post>, ServerFnErro
 
 #[server]
 pub async fn get_posts_by_tag(tag: String) -> Result<Vec<Post>, ServerFnError> {
-  let posts = crate::posts::load_posts();
+  let posts = crate::posts::load_listed_posts();
   Ok(
     posts
       .into_iter()
@@ -52,6 +77,11 @@ pub async fn get_posts_by_tag(tag: String) -> Result<Vec<Post>, ServerFnError> {
   )
 }
 
+#[server]
+pub async fn get_nav_links() -> Result<Vec<NavLink>, ServerFnError> {
+  Ok(crate::pages::nav_links())
+}
+
 #[component]
 fn SiteHeader() -> impl IntoView {
   // Detect system theme preference
@@ -85,6 +115,7 @@ fn SiteHeader() -> impl IntoView {
 
   let is_dark = RwSignal::new(initial_dark);
   let search_ctx = expect_context::<SearchContext>();
+  let lang_ctx = expect_context::<LanguageContext>();
   let navigate = leptos_router::hooks::use_navigate();
 
   // Apply initial theme on mount
@@ -139,6 +170,29 @@ fn SiteHeader() -> impl IntoView {
   };
   let (menu_open, set_menu_open) = signal(false);
 
+  // Links to markdown pages with a `nav_order`
+  let nav_links = Resource::new(|| (), |_| async { get_nav_links().await.unwrap_or_default() });
+  let nav_items = move |close_menu: Option<WriteSignal<bool>>| {
+    view! {
+      <Suspense>
+        {move || nav_links.get().map(|links| links.into_iter().map(|link| view! {
+          <li>
+            <a
+              href=format!("/{}", link.slug)
+              on:click=move |_| {
+                if let Some(close_menu) = close_menu {
+                  close_menu.set(false);
+                }
+              }
+            >
+              {link.title}
+            </a>
+          </li>
+        }).collect_view())}
+      </Suspense>
+    }
+  };
+
   view! {
     <header class="site-header">
       <nav class="container">
@@ -150,7 +204,7 @@ fn SiteHeader() -> impl IntoView {
         <div class="nav-left desktop-nav">
           <ul class="nav-links">
             <li><a href="/archive">"Archive"</a></li>
-            <li><a href="/about">"About"</a></li>
+            {nav_items(None)}
           </ul>
         </div>
 
@@ -174,6 +228,24 @@ fn SiteHeader() -> impl IntoView {
             </svg>
           </div>
 
+          <div class="lang-switcher" role="group" aria-label="Language">
+            {LANGUAGES.iter().map(|language| {
+              let code = language.code;
+              view! {
+                <button
+                  class=move || if lang_ctx.lang.get() == code { "lang-option active" } else { "lang-option" }
+                  lang=language.html_lang
+                  on:click=move |_| {
+                    lang_ctx.set(code);
+                    search_ctx.current_page.set(1);
+                  }
+                >
+                  {language.name}
+                </button>
+              }
+            }).collect_view()}
+          </div>
+
           <button class="theme-toggle" on:click=toggle_theme aria-label="Toggle theme">
             <svg
               class="theme-icon moon-icon"
@@ -227,7 +299,7 @@ fn SiteHeader() -> impl IntoView {
         <div class=move || format!("mobile-menu {}", if menu_open.get() { "open" } else { "" })>
           <ul class="mobile-nav-links">
             <li><a href="/archive" on:click=move |_| set_menu_open.set(false)>"Archive"</a></li>
-            <li><a href="/about" on:click=move |_| set_menu_open.set(false)>"About"</a></li>
+            {nav_items(Some(set_menu_open))}
           </ul>
         </div>
       </nav>
@@ -361,12 +433,30 @@ pub fn App() -> impl IntoView {
   };
   provide_context(search_ctx);
 
+  // Provide the active content language, restoring the reader's choice after hydration
+  let lang_ctx = LanguageContext {
+    lang: RwSignal::new(DEFAULT_LANGUAGE.to_string()),
+  };
+  provide_context(lang_ctx);
+
+  #[cfg(target_arch = "wasm32")]
+  Effect::new(move |_| {
+    if let Some(storage) = window().local_storage().ok().flatten() {
+      if let Ok(Some(code)) = storage.get_item("lang") {
+        if language(&code).is_some() {
+          lang_ctx.lang.set(code);
+        }
+      }
+    }
+  });
+
   view! {
     // content for this welcome page
     <Router>
       // injects a stylesheet into the document <head>
       // id=leptos means cargo-leptos will hot-reload this stylesheet
       <Stylesheet id="leptos" href="/pkg/blog-starter-rs.css"/>
+      <Stylesheet id="highlight" href="/highlight.css"/>
       // sets the document title
       <Title text="Your Blog Name"/>
       <SiteHeader/>
@@ -382,9 +472,13 @@ pub fn App() -> impl IntoView {
         }.into_view()>
           <Route path=StaticSegment("") view=HomePage/>
           <Route path=StaticSegment("archive") view=ArchivePage/>
-          <Route path=StaticSegment("about") view=AboutPage/>
-          <Route path=path!("/posts/:slug") view=PostPage/>
+          // Wait for the post before sending <head>, so its meta tags reach crawlers
+          <Route path=path!("/posts/:slug") view=PostPage ssr=SsrMode::Async/>
           <Route path=path!("/tags/:tag") view=TagPage/>
+          <Route path=path!("/series/:name") view=SeriesPage/>
+          <Route path=path!("/authors/:id") view=AuthorPage/>
+          // Async so a missing page can still answer with a 404 status
+          <Route path=path!("/:page") view=StandalonePage ssr=SsrMode::Async/>
         </Routes>
       </main>
       <SiteFooter/>
@@ -401,6 +495,7 @@ fn HomePage() -> impl IntoView {
     |_| async move { get_post_summaries().await.unwrap_or_default() },
   );
   let search_ctx = expect_context::<SearchContext>();
+  let lang_ctx = expect_context::<LanguageContext>();
   let posts_per_page = 10;
 
   view! {
@@ -452,6 +547,7 @@ fn HomePage() -> impl IntoView {
       <Suspense fallback=move || view! { <p>"Loading posts..."</p> }>
         {move || {
           posts.get().map(|posts| {
+            let posts = prefer_language(posts, &lang_ctx.lang.get());
             let query = search_ctx.query.get().to_lowercase();
             let filtered_posts: Vec<PostSummary> = if query.is_empty() {
               posts
@@ -471,7 +567,7 @@ fn HomePage() -> impl IntoView {
               }.into_any()
             } else {
               let total_posts = filtered_posts.len();
-              let total_pages = (total_posts + posts_per_page - 1) / posts_per_page;
+              let total_pages = total_posts.div_ceil(posts_per_page);
               let current = search_ctx.current_page.get();
               let start_idx = (current - 1) * posts_per_page;
               let paginated_posts: Vec<PostSummary> = filtered_posts.into_iter().skip(start_idx).take(posts_per_page).collect();
@@ -520,7 +616,7 @@ fn PostPage() -> impl IntoView {
   let slug = move || params.read().get("slug").unwrap_or_default();
 
   let post = Resource::new(
-    move || slug(),
+    slug,
     |slug| async move { get_post_by_slug(slug).await.ok().flatten() },
   );
 
@@ -532,14 +628,53 @@ fn PostPage() -> impl IntoView {
             match post_opt {
               Some(post) => {
                 let title = post.metadata.title.clone();
-                let date = post.metadata.date.clone(); // Full datetime for SEO
-                let display_datetime = post.metadata.display_datetime.clone(); // Date and time for display
+                let date = post.metadata.date.to_rfc3339(); // Full datetime for SEO
+                let modified = post.metadata.last_modified().to_rfc3339();
+                let display_datetime = post.metadata.display_datetime(); // Date and time for display
+                let display_updated = post.metadata.display_updated();
+                let reading_time = post.reading_time;
+                // A single heading isn't worth a table of contents
+                let toc = post.toc.clone();
+                let show_toc = post.metadata.toc && toc.iter().map(|entry| entry.count()).sum::<usize>() > 1;
                 let tags = post.metadata.tags.clone();
                 let content = post.content.clone();
                 let description = post.metadata.description.clone();
                 let preview = post.preview.clone();
+                let post_language = language(&post.metadata.lang);
+                let html_lang = post_language.map(|l| l.html_lang).unwrap_or("en");
+                let og_locale = post_language.map(|l| l.locale).unwrap_or("en_US");
+                let translations = post.translations().to_vec();
+                let series = post.series().cloned();
+                let backlinks = post.backlinks().to_vec();
+                let authors = post.authors.clone();
+                let author_names = authors.iter().map(|a| a.name.clone()).collect::<Vec<_>>().join(", ");
                 let page_title = format!("{} | AbletonPilot", title);
                 let og_url = format!("https://your-domain.com/posts/{}", post.slug);
+                let cover = post.cover.clone();
+                // Link previews show the cover, or else a generated card with the title, date
+                // and tags (see src/og_image.rs), or else the site's default image
+                let (og_image, og_image_size, og_image_alt) = match &cover {
+                  Some(cover) => (
+                    if cover.image.src.starts_with('/') {
+                      format!("https://your-domain.com{}", cover.image.src)
+                    } else {
+                      cover.image.src.clone()
+                    },
+                    cover.image.width.zip(cover.image.height),
+                    if cover.alt.is_empty() { title.clone() } else { cover.alt.clone() },
+                  ),
+                  None if post.has_og_card() => (
+                    format!("https://your-domain.com/og/{}.png", post.slug),
+                    Some((1200, 630)),
+                    title.clone(),
+                  ),
+                  None => (
+                    // Change this to your domain and share image
+                    "https://your-domain.com/og-default.png".to_string(),
+                    Some((1200, 630)),
+                    "Your Blog Name".to_string(),
+                  ),
+                };
 
                 // Combine description and preview for better SEO
                 let full_description = if description.is_empty() {
@@ -550,66 +685,94 @@ fn PostPage() -> impl IntoView {
                   format!("{} {}", description, preview)
                 };
 
-                // Structured Data (JSON-LD) for SEO
-                let schema_json = format!(
-                  r#"{{
-                    "@context": "https://schema.org",
-                    "@type": "BlogPosting",
-                    "headline": "{}",
-                    "description": "{}",
-                    "datePublished": "{}",
-                    "author": {{
-                      "@type": "Person",
-                      "name": "Your Name",
-                      "url": "https://your-domain.com/about"
-                    }},
-                    "publisher": {{
-                      "@type": "Organization",
-                      "name": "Your Blog Name",
-                      "url": "https://your-domain.com"
-                    }},
-                    "mainEntityOfPage": {{
-                      "@type": "WebPage",
-                      "@id": "{}"
-                    }},
-                    "keywords": "{}"
-                  }}"#,
-                  title.replace('"', "\\\""),
-                  description.replace('"', "\\\""),
-                  date,
-                  og_url,
-                  tags.join(", ")
-                );
+                // Structured Data (JSON-LD) for SEO. `<` is escaped so no value can close the
+                // surrounding <script> tag.
+                let mut schema = serde_json::json!({
+                  "@context": "https://schema.org",
+                  "@type": "BlogPosting",
+                  "headline": title,
+                  "description": description,
+                  "datePublished": date,
+                  "dateModified": modified,
+                  "wordCount": reading_time.words,
+                  "timeRequired": reading_time.iso_duration(),
+                  "author": authors.iter().map(|author| serde_json::json!({
+                    "@type": "Person",
+                    "name": author.name,
+                    "url": author.url()
+                  })).collect::<Vec<_>>(),
+                  "publisher": {
+                    "@type": "Organization",
+                    "name": "Your Blog Name",
+                    "url": "https://your-domain.com"
+                  },
+                  "mainEntityOfPage": {
+                    "@type": "WebPage",
+                    "@id": og_url
+                  },
+                  "keywords": tags.join(", ")
+                });
+                // Series membership
+                if let Some(series) = &series {
+                  schema["isPartOf"] = serde_json::json!({
+                    "@type": "CreativeWorkSeries",
+                    "name": series.name,
+                    "url": format!("https://your-domain.com/series/{}", series.slug)
+                  });
+                  schema["position"] = serde_json::json!(series.position);
+                }
+                let schema_json = schema.to_string().replace('<', "\\u003c");
 
                 view! {
                   <Title text=page_title.clone()/>
                   <Meta name="description" content=full_description.clone()/>
                   <Meta name="keywords" content=tags.join(", ")/>
-                  <Meta name="author" content="Your Name"/>
+                  <Meta name="author" content=author_names/>
                   <Meta name="application-name" content="Your Application Name"/>
                   <Meta property="og:type" content="article"/>
                   <Meta property="og:title" content=title.clone()/>
                   <Meta property="og:description" content=full_description.clone()/>
                   <Meta property="og:url" content=og_url.clone()/>
                   <Meta property="og:site_name" content="Your Blog Name"/>
-                  <Meta property="og:locale" content="en_US"/>
+                  <Meta property="og:locale" content=og_locale/>
+                  <Meta property="og:image" content=og_image.clone()/>
+                  {og_image_size.map(|(width, height)| view! {
+                    <Meta property="og:image:width" content=width.to_string()/>
+                    <Meta property="og:image:height" content=height.to_string()/>
+                  })}
+                  <Meta property="og:image:alt" content=og_image_alt.clone()/>
+                  {translations.iter().filter_map(|translation| language(&translation.lang)).map(|l| view! {
+                    <Meta property="og:locale:alternate" content=l.locale/>
+                  }).collect_view()}
                   <Meta property="article:published_time" content=date.clone()/>
-                  <Meta property="article:author" content="Your Name"/>
+                  <Meta property="article:modified_time" content=modified.clone()/>
+                  {authors.iter().map(|author| view! {
+                    <Meta property="article:author" content=author.url()/>
+                  }).collect_view()}
                   <Meta property="article:tag" content=tags.join(", ")/>
                   <Meta name="twitter:card" content="summary_large_image"/>
                   <Meta name="twitter:title" content=title.clone()/>
                   <Meta name="twitter:description" content=full_description.clone()/>
                   <Meta name="twitter:url" content=og_url.clone()/>
+                  <Meta name="twitter:image" content=og_image.clone()/>
+                  <Meta name="twitter:image:alt" content=og_image_alt/>
                   <Meta name="twitter:site" content="@YourTwitterHandle"/>
                   <link rel="canonical" href=og_url.clone()/>
                   <Meta name="robots" content="index, follow"/>
 
-                  <article class="post-detail">
+                  <article class="post-detail" lang=html_lang>
                     <script type="application/ld+json" inner_html=schema_json></script>
                     <header>
                       <h1>{title}</h1>
                       <div class="post-meta">
-                        <span class="date">{display_datetime}</span>
+                        <time class="date" datetime=date.clone()>{display_datetime}</time>
+                        {display_updated.map(|updated| view! {
+                          <time class="updated" datetime=modified.clone()>"Updated " {updated}</time>
+                        })}
+                        <span class="reading-time" title=format!("{} words", reading_time.words)>
+                          {format!("{} min read", reading_time.minutes)}
+                        </span>
+                        {(!authors.is_empty()).then(|| view! { <Byline authors=authors.clone()/> })}
                         <span class="tags">
                           {tags.iter().map(|tag| {
                             let tag_text = tag.clone();
@@ -620,8 +783,70 @@ fn PostPage() -> impl IntoView {
                           }).collect_view()}
                         </span>
                       </div>
+                      {(!translations.is_empty()).then(|| {
+                        let lang_ctx = expect_context::<LanguageContext>();
+                        view! {
+                          <nav class="post-translations" aria-label="Translations">
+                            <span>"Also available in: "</span>
+                            {translations.iter().map(|translation| {
+                              let code = translation.lang.clone();
+                              let target = language(&code);
+                              let name = target.map(|l| l.name).unwrap_or(translation.lang.as_str()).to_string();
+                              view! {
+                                <a
+                                  href=format!("/posts/{}", translation.slug)
+                                  hreflang=target.map(|l| l.html_lang)
+                                  title=translation.title.clone()
+                                  on:click=move |_| lang_ctx.set(&code)
+                                >
+                                  {name}
+                                </a>
+                              }
+                            }).collect_view()}
+                          </nav>
+                        }
+                      })}
+                      {cover.map(|cover| view! {
+                        <figure class="post-cover">
+                          <Picture image=cover.image alt=cover.alt sizes=CONTENT_SIZES eager=true/>
+                          {cover.caption.map(|caption| view! { <figcaption>{caption}</figcaption> })}
+                        </figure>
+                      })}
                     </header>
+                    {series.map(|series| view! {
+                      <aside class="series-box">
+                        <div class="series-title">
+                          <a href=format!("/series/{}", series.slug)>{series.name.clone()}</a>
+                          <span class="series-position">
+                            {format!("Part {} of {}", series.position, series.total())}
+                          </span>
+                        </div>
+                        <ol>
+                          {series.parts.iter().enumerate().map(|(index, part)| {
+                            if index + 1 == series.position {
+                              view! { <li class="current">{part.title.clone()}</li> }.into_any()
+                            } else {
+                              view! {
+                                <li><a href=format!("/posts/{}", part.slug)>{part.title.clone()}</a></li>
+                              }.into_any()
+                            }
+                          }).collect_view()}
+                        </ol>
+                      </aside>
+                    })}
+                    {show_toc.then(|| view! { <TableOfContents entries=toc/> })}
                     <div class="post-content" inner_html=content></div>
+                    <CodeBlockTools/>
+                    {(!backlinks.is_empty()).then(|| view! {
+                      <aside class="backlinks" aria-label="Referenced by">
+                        <h2>"Referenced by"</h2>
+                        <ul>
+                          {backlinks.iter().map(|backlink| view! {
+                            <li><a href=format!("/posts/{}", backlink.slug)>{backlink.title.clone()}</a></li>
+                          }).collect_view()}
+                        </ul>
+                      </aside>
+                    })}
                     <a href="/" class="back-link">"← Back to posts"</a>
 
                     // Comments section
@@ -656,9 +881,10 @@ fn TagPage() -> impl IntoView {
   let tag = move || params.read().get("tag").unwrap_or_default();
 
   let posts = Resource::new(
-    move || tag(),
+    tag,
     |tag| async move { get_posts_by_tag_summaries(tag).await.unwrap_or_default() },
   );
+  let lang_ctx = expect_context::<LanguageContext>();
 
   view! {
     {move || {
@@ -689,6 +915,7 @@ fn TagPage() -> impl IntoView {
       <Suspense fallback=move || view! { <p>"Loading posts..."</p> }>
         {move || {
           posts.get().map(|posts| {
+            let posts = prefer_language(posts, &lang_ctx.lang.get());
             if posts.is_empty() {
               view! {
                 <div class="no-posts">
@@ -0,0 +1,71 @@
+// Author registry loaded from `authors.yaml`
+
+use serde::{Deserialize, Serialize};
+
+/// A person who writes posts, as listed in `authors.yaml`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Author {
+  /// Referenced from the `authors` front matter key and used in `/authors/<id>`
+  pub id: String,
+  pub name: String,
+  #[serde(default)]
+  pub bio: String,
+  /// Avatar image URL
+  #[serde(default)]
+  pub avatar: Option<String>,
+  /// Only used for the RSS `<author>` element, which requires an email address
+  #[serde(default)]
+  pub email: Option<String>,
+  #[serde(default)]
+  pub links: Vec<AuthorLink>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct AuthorLink {
+  pub label: String,
+  pub url: String,
+}
+
+impl Author {
+  pub fn url(&self) -> String {
+    format!("https://your-domain.com/authors/{}", self.id)
+  }
+}
+
+/// All registered authors; the first one is the default for posts without `authors`
+#[cfg(feature = "ssr")]
+pub fn load_authors() -> Vec<Author> {
+  use std::sync::LazyLock;
+
+  static AUTHORS: LazyLock<Vec<Author>> = LazyLock::new(|| read_authors_file("authors.yaml"));
+
+  AUTHORS.clone()
+}
+
+#[cfg(feature = "ssr")]
+fn read_authors_file(path: &str) -> Vec<Author> {
+  let Ok(content) = std::fs::read_to_string(path) else {
+    eprintln!("Authors file {} does not exist", path);
+    return vec![];
+  };
+
+  let authors: Vec<Author> = match serde_yaml::from_str(&content) {
+    Ok(authors) => authors,
+    Err(e) => {
+      eprintln!("Error parsing {}: {}", path, e);
+      return vec![];
+    }
+  };
+
+  let mut seen = std::collections::HashSet::new();
+  authors
+    .into_iter()
+    .filter(|author| {
+      let first = seen.insert(author.id.clone());
+      if !first {
+        eprintln!("Ignoring duplicate author id `{}` in {}", author.id, path);
+      }
+      first
+    })
+    .collect()
+}
@@ -1,4 +1,6 @@
-use crate::posts::PostSummary;
+use crate::app::LanguageContext;
+use crate::posts::{prefer_language, PostSummary};
+use chrono::Datelike;
 use leptos::prelude::*;
 use leptos_meta::{Meta, Title};
 use std::collections::BTreeMap;
@@ -14,6 +16,7 @@ pub fn ArchivePage() -> impl IntoView {
     || (),
     |_| async move { get_posts_for_archive().await.unwrap_or_default() },
   );
+  let lang_ctx = expect_context::<LanguageContext>();
 
   view! {
     <Title text="Archive - Your Blog"/>
@@ -45,6 +48,7 @@ pub fn ArchivePage() -> impl IntoView {
         <Suspense fallback=move || view! { <p>"Loading archive..."</p> }>
           {move || {
             posts.get().map(|posts| {
+              let posts = prefer_language(posts, &lang_ctx.lang.get());
               let posts_by_year = create_posts_by_year(&posts);
 
               view! {
@@ -55,12 +59,7 @@ pub fn ArchivePage() -> impl IntoView {
                         <h2 class="year-title">{year}</h2>
                         <div class="posts-list">
                           {year_posts.into_iter().map(|post| {
-                            let date_parts: Vec<&str> = post.metadata.date.split('-').collect();
-                            let month = if date_parts.len() >= 2 {
-                              get_month_name(date_parts[1])
-                            } else {
-                              "Unknown"
-                            };
+                            let month = get_month_name(post.metadata.date.month());
 
                             view! {
                               <article class="archive-post">
@@ -89,46 +88,35 @@ pub fn ArchivePage() -> impl IntoView {
     </div>
   }
 }
-fn create_posts_by_year(posts: &[PostSummary]) -> BTreeMap<String, Vec<PostSummary>> {
-  let mut posts_by_year: BTreeMap<String, Vec<PostSummary>> = BTreeMap::new();
+fn create_posts_by_year(posts: &[PostSummary]) -> Vec<(i32, Vec<PostSummary>)> {
+  let mut posts_by_year: BTreeMap<i32, Vec<PostSummary>> = BTreeMap::new();
 
   for post in posts {
-    let year = post
-      .metadata
-      .date
-      .split('-')
-      .next()
-      .unwrap_or("Unknown")
-      .to_string();
+    let year = post.metadata.date.year();
     posts_by_year
       .entry(year)
-      .or_insert_with(Vec::new)
+      .or_default()
       .push(post.clone());
   }
 
   // Sort by year descending
-  posts_by_year
-    .into_iter()
-    .collect::<BTreeMap<_, _>>()
-    .into_iter()
-    .rev()
-    .collect()
+  posts_by_year.into_iter().rev().collect()
 }
 
-fn get_month_name(month_num: &str) -> &'static str {
-  match month_num {
-    "01" => "January",
-    "02" => "February",
-    "03" => "March",
-    "04" => "April",
-    "05" => "May",
-    "06" => "June",
-    "07" => "July",
-    "08" => "August",
-    "09" => "September",
-    "10" => "October",
-    "11" => "November",
-    "12" => "December",
+fn get_month_name(month: u32) -> &'static str {
+  match month {
+    1 => "January",
+    2 => "February",
+    3 => "March",
+    4 => "April",
+    5 => "May",
+    6 => "June",
+    7 => "July",
+    8 => "August",
+    9 => "September",
+    10 => "October",
+    11 => "November",
+    12 => "December",
     _ => "Unknown",
   }
 }
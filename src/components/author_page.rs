@@ -0,0 +1,109 @@
+use crate::app::LanguageContext;
+use crate::authors::Author;
+use crate::components::PostSummaryCard;
+use crate::posts::{prefer_language, PostSummary};
+use leptos::prelude::*;
+use leptos_meta::{Meta, Title};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuthorProfile {
+  pub author: Author,
+  pub posts: Vec<PostSummary>,
+}
+
+#[server]
+pub async fn get_author_profile(id: String) -> Result<Option<AuthorProfile>, ServerFnError> {
+  let Some(author) = crate::authors::load_authors()
+    .into_iter()
+    .find(|author| author.id == id)
+  else {
+    return Ok(None);
+  };
+
+  let posts = crate::posts::load_post_summaries()
+    .into_iter()
+    .filter(|post| post.metadata.authors.contains(&id))
+    .collect();
+  Ok(Some(AuthorProfile { author, posts }))
+}
+
+#[component]
+pub fn AuthorPage() -> impl IntoView {
+  let params = leptos_router::hooks::use_params_map();
+  let id = move || params.read().get("id").unwrap_or_default();
+
+  let profile = Resource::new(id, |id| async move {
+    get_author_profile(id).await.ok().flatten()
+  });
+  let lang_ctx = expect_context::<LanguageContext>();
+
+  view! {
+    <div class="container">
+      <Suspense fallback=move || view! { <p>"Loading author..."</p> }>
+        {move || {
+          profile.get().map(|profile| match profile {
+            Some(AuthorProfile { author, posts }) => {
+              let posts = prefer_language(posts, &lang_ctx.lang.get());
+              let page_title = format!("{} - Your Blog", author.name);
+              let description = if author.bio.is_empty() {
+                format!("Posts by {} on Your Blog", author.name)
+              } else {
+                author.bio.clone()
+              };
+              let url = author.url();
+
+              view! {
+                <Title text=page_title.clone()/>
+                <Meta name="description" content=description.clone()/>
+                <Meta property="og:type" content="profile"/>
+                <Meta property="og:title" content=page_title/>
+                <Meta property="og:description" content=description/>
+                <Meta property="og:url" content=url.clone()/>
+                <Meta property="og:site_name" content="Your Blog Name"/>
+                <link rel="canonical" href=url/>
+
+                <header class="author-header">
+                  {author.avatar.clone().map(|avatar| view! {
+                    <img class="author-avatar" src=avatar alt=author.name.clone() width="96" height="96"/>
+                  })}
+                  <div class="author-info">
+                    <h1>{author.name.clone()}</h1>
+                    <p class="author-bio">{author.bio.clone()}</p>
+                    <div class="author-links">
+                      {author.links.iter().map(|link| view! {
+                        <a href=link.url.clone() target="_blank" rel="noopener noreferrer me">{link.label.clone()}</a>
+                      }).collect_view()}
+                    </div>
+                  </div>
+                </header>
+
+                {if posts.is_empty() {
+                  view! {
+                    <div class="no-posts">
+                      <p>"No posts by this author yet."</p>
+                    </div>
+                  }.into_any()
+                } else {
+                  view! {
+                    <div class="posts-list">
+                      {posts.into_iter().map(|post| view! { <PostSummaryCard post=post /> }).collect_view()}
+                    </div>
+                  }.into_any()
+                }}
+              }.into_any()
+            }
+            None => view! {
+              <Title text="Author Not Found - Your Blog"/>
+              <div class="not-found">
+                <h1>"Author Not Found"</h1>
+                <p>"The author you are looking for does not exist."</p>
+                <a href="/">"← Back to posts"</a>
+              </div>
+            }.into_any(),
+          })
+        }}
+      </Suspense>
+    </div>
+  }
+}
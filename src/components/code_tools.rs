@@ -0,0 +1,174 @@
+use leptos::prelude::*;
+
+/// Adds a language label and a copy button to the highlighted code blocks in `.post-content`.
+///
+/// Renders nothing itself; place it after the content so the blocks exist when it runs.
+#[component]
+pub fn CodeBlockTools() -> impl IntoView {
+  Effect::new(move |_| {
+    #[cfg(feature = "hydrate")]
+    enhance_code_blocks();
+  });
+}
+
+#[cfg(feature = "hydrate")]
+fn enhance_code_blocks() {
+  use wasm_bindgen::prelude::*;
+  use wasm_bindgen::JsCast;
+
+  let Some(document) = web_sys::window().and_then(|window| window.document()) else {
+    return;
+  };
+  let Ok(blocks) = document.query_selector_all(".post-content .code-block:not([data-enhanced])")
+  else {
+    return;
+  };
+
+  for index in 0..blocks.length() {
+    let Some(block) = blocks
+      .item(index)
+      .and_then(|node| node.dyn_into::<web_sys::Element>().ok())
+    else {
+      continue;
+    };
+    block.set_attribute("data-enhanced", "").ok();
+
+    let Ok(tools) = document.create_element("div") else {
+      continue;
+    };
+    tools.set_class_name("code-tools");
+
+    if let Some(lang) = block.get_attribute("data-lang") {
+      if let Ok(label) = document.create_element("span") {
+        label.set_class_name("code-lang");
+        label.set_text_content(Some(&lang));
+        tools.append_child(&label).ok();
+      }
+    }
+
+    let Some(button) = document
+      .create_element("button")
+      .ok()
+      .and_then(|button| button.dyn_into::<web_sys::HtmlElement>().ok())
+    else {
+      continue;
+    };
+    button.set_class_name("code-copy");
+    button.set_attribute("type", "button").ok();
+    button.set_attribute("aria-label", "Copy code").ok();
+    button.set_attribute("aria-live", "polite").ok();
+    button.set_text_content(Some("Copy"));
+
+    // Line numbers are CSS counters, so the text content is exactly the source
+    let code_block = block.clone();
+    let copy_button = button.clone();
+    let on_click = Closure::<dyn FnMut()>::new(move || {
+      let text = code_block
+        .query_selector("code")
+        .ok()
+        .flatten()
+        .and_then(|code| code.text_content())
+        .unwrap_or_default();
+      copy_text(text.trim_end_matches('\n'), &copy_button);
+    });
+    button
+      .add_event_listener_with_callback("click", on_click.as_ref().unchecked_ref())
+      .ok();
+    on_click.forget();
+
+    tools.append_child(&button).ok();
+    block.append_child(&tools).ok();
+  }
+}
+
+/// Copy with the Clipboard API, falling back to a hidden textarea where it's unavailable (plain HTTP)
+#[cfg(feature = "hydrate")]
+fn copy_text(text: &str, button: &web_sys::HtmlElement) {
+  use wasm_bindgen::prelude::*;
+
+  let Some(window) = web_sys::window() else {
+    return;
+  };
+  let navigator = window.navigator();
+  let has_clipboard = js_sys::Reflect::get(&navigator, &"clipboard".into())
+    .map(|clipboard| !clipboard.is_undefined())
+    .unwrap_or(false);
+
+  if !has_clipboard {
+    show_result(button, copy_with_textarea(text));
+    return;
+  }
+
+  let on_copied = {
+    let button = button.clone();
+    Closure::once(move |_: JsValue| show_result(&button, true))
+  };
+  let on_failed = {
+    let button = button.clone();
+    let text = text.to_string();
+    Closure::once(move |_: JsValue| show_result(&button, copy_with_textarea(&text)))
+  };
+  let _ = navigator
+    .clipboard()
+    .write_text(text)
+    .then2(&on_copied, &on_failed);
+  on_copied.forget();
+  on_failed.forget();
+}
+
+#[cfg(feature = "hydrate")]
+fn copy_with_textarea(text: &str) -> bool {
+  use wasm_bindgen::JsCast;
+
+  let Some(document) = web_sys::window().and_then(|window| window.document()) else {
+    return false;
+  };
+  let Some(body) = document.body() else {
+    return false;
+  };
+  let Some(textarea) = document
+    .create_element("textarea")
+    .ok()
+    .and_then(|textarea| textarea.dyn_into::<web_sys::HtmlTextAreaElement>().ok())
+  else {
+    return false;
+  };
+
+  textarea.set_value(text);
+  textarea.set_attribute("readonly", "").ok();
+  textarea
+    .set_attribute("style", "position: fixed; top: 0; left: 0; opacity: 0;")
+    .ok();
+  if body.append_child(&textarea).is_err() {
+    return false;
+  }
+  textarea.select();
+  let copied = document
+    .dyn_ref::<web_sys::HtmlDocument>()
+    .and_then(|document| document.exec_command("copy").ok())
+    .unwrap_or(false);
+  textarea.remove();
+  copied
+}
+
+/// "Copied!" (or "Copy failed") for two seconds, then back to "Copy"
+#[cfg(feature = "hydrate")]
+fn show_result(button: &web_sys::HtmlElement, copied: bool) {
+  use wasm_bindgen::prelude::*;
+  use wasm_bindgen::JsCast;
+
+  let class = if copied { "copied" } else { "copy-failed" };
+  button.set_text_content(Some(if copied { "Copied!" } else { "Copy failed" }));
+  button.class_list().add_1(class).ok();
+
+  let button = button.clone();
+  let reset = Closure::once_into_js(move || {
+    button.set_text_content(Some("Copy"));
+    button.class_list().remove_1(class).ok();
+  });
+  if let Some(window) = web_sys::window() {
+    window
+      .set_timeout_with_callback_and_timeout_and_arguments_0(reset.unchecked_ref(), 2000)
+      .ok();
+  }
+}
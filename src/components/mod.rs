@@ -1,9 +1,19 @@
-pub mod about_page;
 pub mod archive_page;
+pub mod author_page;
+pub mod code_tools;
 pub mod giscus;
+pub mod picture;
 pub mod post_card;
+pub mod series_page;
+pub mod standalone_page;
+pub mod table_of_contents;
 
-pub use about_page::AboutPage;
 pub use archive_page::ArchivePage;
+pub use author_page::AuthorPage;
+pub use code_tools::CodeBlockTools;
 pub use giscus::Giscus;
-pub use post_card::PostSummaryCard;
+pub use picture::Picture;
+pub use post_card::{Byline, PostSummaryCard};
+pub use series_page::SeriesPage;
+pub use standalone_page::StandalonePage;
+pub use table_of_contents::TableOfContents;
@@ -0,0 +1,32 @@
+use crate::images::ResponsiveImage;
+use leptos::prelude::*;
+
+/// `<picture>` for a [`ResponsiveImage`], with its WebP variants when there are any.
+///
+/// Lazy-loaded unless `eager`, which also asks for it early; use that above the fold.
+#[component]
+pub fn Picture(
+  image: ResponsiveImage,
+  alt: String,
+  sizes: &'static str,
+  #[prop(optional)] eager: bool,
+) -> impl IntoView {
+  view! {
+    <picture>
+      {image.webp_srcset.map(|srcset| view! {
+        <source type="image/webp" srcset=srcset sizes=sizes/>
+      })}
+      <img
+        src=image.src
+        srcset=image.srcset
+        sizes=sizes
+        width=image.width
+        height=image.height
+        alt=alt
+        loading=if eager { "eager" } else { "lazy" }
+        fetchpriority=eager.then_some("high")
+        decoding="async"
+      />
+    </picture>
+  }
+}
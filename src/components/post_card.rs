@@ -1,29 +1,46 @@
+use crate::authors::Author;
+use crate::components::Picture;
 use crate::posts::PostSummary;
 use leptos::prelude::*;
 
+/// Thumbnails are 170px wide next to the card, or full width on narrow screens
+const THUMBNAIL_SIZES: &str = "(max-width: 768px) 100vw, 170px";
+
 #[component]
 pub fn PostSummaryCard(post: PostSummary) -> impl IntoView {
   let slug = post.slug.clone();
   let title = post.metadata.title.clone();
-  let display_date = post.metadata.display_date.clone();
+  let display_date = post.metadata.display_date();
+  let reading_time = format!("{} min read", post.reading_time.minutes);
   let description = post.metadata.description.clone();
   let tags = post.metadata.tags.clone();
+  let authors = post.authors.clone();
   let thumbnail = post.thumbnail.clone();
   let has_thumbnail = thumbnail.is_some();
+  let is_draft = post.metadata.draft;
+  let is_scheduled = post.metadata.is_scheduled_at(chrono::Utc::now());
 
   view! {
     <article class=move || if has_thumbnail { "post-card has-thumbnail" } else { "post-card" }>
-      {thumbnail.map(|thumb_url| view! {
+      {thumbnail.map(|thumb| view! {
         <div class="post-thumbnail">
           <a href=format!("/posts/{}", slug.clone())>
-            <img src=thumb_url alt=title.clone() loading="lazy"/>
+            <Picture
+              image=thumb.image
+              alt=if thumb.alt.is_empty() { title.clone() } else { thumb.alt }
+              sizes=THUMBNAIL_SIZES
+            />
           </a>
         </div>
       })}
       <div class="post-content">
         <h2><a href=format!("/posts/{}", slug)>{title}</a></h2>
         <div class="post-meta">
+          {is_draft.then(|| view! { <span class="status-badge draft">"DRAFT"</span> })}
+          {(!is_draft && is_scheduled).then(|| view! { <span class="status-badge scheduled">"SCHEDULED"</span> })}
           <span class="date">{display_date}</span>
+          <span class="reading-time">{reading_time}</span>
+          {(!authors.is_empty()).then(|| view! { <Byline authors=authors/> })}
           <span class="tags">
             {tags.iter().map(|tag| {
               let tag_text = tag.clone();
@@ -39,3 +56,19 @@ pub fn PostSummaryCard(post: PostSummary) -> impl IntoView {
     </article>
   }
 }
+
+/// "By A, B" with links to the author pages
+#[component]
+pub fn Byline(authors: Vec<Author>) -> impl IntoView {
+  let count = authors.len();
+
+  view! {
+    <span class="byline">
+      "By "
+      {authors.into_iter().enumerate().map(|(index, author)| view! {
+        <a href=format!("/authors/{}", author.id) rel="author">{author.name}</a>
+        {(index + 1 < count).then_some(", ")}
+      }).collect_view()}
+    </span>
+  }
+}
@@ -0,0 +1,89 @@
+use crate::app::LanguageContext;
+use crate::posts::{prefer_language, PostSummary};
+use leptos::prelude::*;
+use leptos_meta::{Meta, Title};
+
+#[server]
+pub async fn get_series_parts(slug: String) -> Result<Vec<PostSummary>, ServerFnError> {
+  let mut parts: Vec<PostSummary> = crate::posts::load_post_summaries()
+    .into_iter()
+    .filter(|post| {
+      post
+        .metadata
+        .series
+        .as_deref()
+        .is_some_and(|name| crate::posts::series_slug(name) == slug)
+    })
+    .collect();
+  parts.sort_by_key(|post| post.metadata.series_sort_key());
+  Ok(parts)
+}
+
+#[component]
+pub fn SeriesPage() -> impl IntoView {
+  let params = leptos_router::hooks::use_params_map();
+  let slug = move || params.read().get("name").unwrap_or_default();
+
+  let parts = Resource::new(slug, |slug| async move {
+    get_series_parts(slug).await.unwrap_or_default()
+  });
+  let lang_ctx = expect_context::<LanguageContext>();
+
+  view! {
+    <div class="container">
+      <div class="series-page">
+        <Suspense fallback=move || view! { <p>"Loading series..."</p> }>
+          {move || {
+            parts.get().map(|parts| {
+              let parts = prefer_language(parts, &lang_ctx.lang.get());
+              let Some(name) = parts.first().and_then(|post| post.metadata.series.clone()) else {
+                return view! {
+                  <Title text="Series Not Found - Your Blog"/>
+                  <div class="not-found">
+                    <h1>"Series Not Found"</h1>
+                    <p>"The series you are looking for does not exist."</p>
+                    <a href="/">"← Back to posts"</a>
+                  </div>
+                }.into_any();
+              };
+              let total = parts.len();
+              let page_title = format!("{} - Your Blog", name);
+              let description = format!("All {} parts of the series '{}'", total, name);
+              let url = format!("https://your-domain.com/series/{}", slug());
+
+              view! {
+                <Title text=page_title.clone()/>
+                <Meta name="description" content=description.clone()/>
+                <Meta property="og:type" content="website"/>
+                <Meta property="og:title" content=page_title/>
+                <Meta property="og:description" content=description/>
+                <Meta property="og:url" content=url.clone()/>
+                <Meta property="og:site_name" content="Your Blog Name"/>
+                <link rel="canonical" href=url/>
+
+                <header class="series-header">
+                  <span class="series-label">"Series"</span>
+                  <h1>{name}</h1>
+                  <p>{format!("{} parts", total)}</p>
+                </header>
+                <ol class="series-parts">
+                  {parts.into_iter().enumerate().map(|(index, post)| view! {
+                    <li class="series-part">
+                      <span class="part-number">{format!("Part {}", index + 1)}</span>
+                      <div class="post-info">
+                        <h3><a href=format!("/posts/{}", post.slug)>{post.metadata.title}</a></h3>
+                        <p class="post-description">{post.metadata.description}</p>
+                      </div>
+                    </li>
+                  }).collect_view()}
+                </ol>
+              }.into_any()
+            })
+          }}
+        </Suspense>
+
+        <a href="/" class="back-link">"← Back to posts"</a>
+      </div>
+    </div>
+  }
+}
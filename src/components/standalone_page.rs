@@ -0,0 +1,73 @@
+use crate::components::CodeBlockTools;
+use crate::pages::Page;
+use leptos::prelude::*;
+use leptos_meta::{Meta, Title};
+
+#[server]
+pub async fn get_page(slug: String) -> Result<Option<Page>, ServerFnError> {
+  Ok(crate::pages::load_page(&slug))
+}
+
+/// A markdown page from `pages/`, served at `/<slug>`
+#[component]
+pub fn StandalonePage() -> impl IntoView {
+  let params = leptos_router::hooks::use_params_map();
+  let slug = move || params.read().get("page").unwrap_or_default();
+
+  let page = Resource::new(slug, |slug| async move { get_page(slug).await.ok().flatten() });
+
+  view! {
+    <div class="container">
+      <Suspense fallback=move || view! { <p>"Loading..."</p> }>
+        {move || {
+          page.get().map(|page| match page {
+            Some(page) => {
+              let page_title = format!("{} - Your Blog Name", page.metadata.title);
+              let description = page.metadata.description.clone();
+              let url = page.url();
+
+              view! {
+                <Title text=page_title.clone()/>
+                <Meta name="description" content=description.clone()/>
+                <Meta property="og:type" content="website"/>
+                <Meta property="og:title" content=page_title.clone()/>
+                <Meta property="og:description" content=description.clone()/>
+                <Meta property="og:url" content=url.clone()/>
+                <Meta property="og:site_name" content="Your Blog Name"/>
+                <Meta name="twitter:card" content="summary"/>
+                <Meta name="twitter:title" content=page_title/>
+                <Meta name="twitter:description" content=description/>
+                <link rel="canonical" href=url/>
+
+                <article class="post-detail standalone-page">
+                  <header>
+                    <h1>{page.metadata.title}</h1>
+                  </header>
+                  <div class="post-content" inner_html=page.content></div>
+                  <CodeBlockTools/>
+                  <a href="/" class="back-link">"← Back to posts"</a>
+                </article>
+              }.into_any()
+            }
+            None => {
+              // Unknown URLs must not look like pages to crawlers
+              #[cfg(feature = "ssr")]
+              if let Some(response) = use_context::<leptos_axum::ResponseOptions>() {
+                response.set_status(axum::http::StatusCode::NOT_FOUND);
+              }
+
+              view! {
+                <Title text="Page Not Found - Your Blog Name"/>
+                <div class="not-found">
+                  <h1>"404"</h1>
+                  <p>"Page not found."</p>
+                  <a href="/">"← Back to home"</a>
+                </div>
+              }.into_any()
+            }
+          })
+        }}
+      </Suspense>
+    </div>
+  }
+}
@@ -0,0 +1,32 @@
+use crate::posts::TocEntry;
+use leptos::prelude::*;
+
+/// Table of contents for a post: a sticky sidebar on wide screens and a collapsible
+/// block above the content on narrow ones
+#[component]
+pub fn TableOfContents(entries: Vec<TocEntry>) -> impl IntoView {
+  view! {
+    <nav class="toc toc-sidebar" aria-label="Table of contents">
+      <p class="toc-title">"Contents"</p>
+      {toc_list(&entries)}
+    </nav>
+    <details class="toc toc-collapsible">
+      <summary class="toc-title">"Contents"</summary>
+      {toc_list(&entries)}
+    </details>
+  }
+}
+
+fn toc_list(entries: &[TocEntry]) -> AnyView {
+  view! {
+    <ol>
+      {entries.iter().map(|entry| view! {
+        <li>
+          <a href=format!("#{}", entry.id)>{entry.title.clone()}</a>
+          {(!entry.children.is_empty()).then(|| toc_list(&entry.children))}
+        </li>
+      }).collect_view()}
+    </ol>
+  }
+  .into_any()
+}
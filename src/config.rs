@@ -0,0 +1,168 @@
+// Site configuration read from environment variables at startup
+
+use chrono_tz::Tz;
+use std::path::PathBuf;
+use std::sync::LazyLock;
+
+#[derive(Debug, Clone)]
+pub struct SiteConfig {
+  /// Include drafts and scheduled posts in every listing (preview mode).
+  ///
+  /// Off unless `BLOG_SHOW_DRAFTS=true`, so a deployment never publishes drafts by accident.
+  pub show_drafts: bool,
+  /// Timezone for dates written without an offset and for displaying dates.
+  ///
+  /// An IANA name such as `Asia/Seoul`, set with `BLOG_TIMEZONE`; defaults to UTC.
+  pub timezone: Tz,
+  /// Directory with extra `.sublime-syntax` and `.tmTheme` files for code highlighting.
+  ///
+  /// Set with `BLOG_HIGHLIGHT_DIR`; defaults to `highlight`. It's fine for it not to exist.
+  pub highlight_dir: PathBuf,
+  /// Code highlighting theme used in dark mode, set with `BLOG_HIGHLIGHT_THEME_DARK`.
+  pub highlight_theme_dark: String,
+  /// Code highlighting theme used in light mode, set with `BLOG_HIGHLIGHT_THEME_LIGHT`.
+  pub highlight_theme_light: String,
+  /// Directory where resized and WebP versions of post images are written.
+  ///
+  /// Set with `BLOG_IMAGE_CACHE_DIR`; defaults to `image-cache`. Files are named by content
+  /// hash, so the directory can be deleted at any time and is regenerated on demand.
+  pub image_cache_dir: PathBuf,
+  /// Widths in pixels to resize post images to, for `srcset`.
+  ///
+  /// A comma-separated list set with `BLOG_IMAGE_WIDTHS`; defaults to `400,800,1200,1600`.
+  /// Images are never scaled up.
+  pub image_widths: Vec<u32>,
+  /// Fonts for the generated Open Graph cards, tried in order for each character.
+  ///
+  /// A comma-separated list of `.ttf`/`.otf`/`.ttc` paths set with `BLOG_OG_FONTS`. Add a CJK
+  /// font such as Noto Sans CJK to render Korean, Japanese or Chinese titles. Defaults to
+  /// whichever of DejaVu Sans Bold, Noto Sans CJK Bold and Nanum Gothic Bold are installed.
+  pub og_fonts: Vec<PathBuf>,
+  /// Strip scripts and other unsafe HTML from rendered posts and pages.
+  ///
+  /// On by default; set `BLOG_SANITIZE_HTML=false` if every author is trusted and posts need
+  /// raw HTML outside the allowlist in `src/sanitize.rs`.
+  pub sanitize_html: bool,
+  /// Hosts that `<iframe>` embeds may load from.
+  ///
+  /// A comma-separated list set with `BLOG_IFRAME_HOSTS`; defaults to YouTube (including
+  /// youtube-nocookie.com), Vimeo and CodePen.
+  pub iframe_hosts: Vec<String>,
+}
+
+impl SiteConfig {
+  fn from_env() -> Self {
+    SiteConfig {
+      show_drafts: env_flag("BLOG_SHOW_DRAFTS").unwrap_or(false),
+      timezone: env_timezone("BLOG_TIMEZONE").unwrap_or(Tz::UTC),
+      highlight_dir: std::env::var("BLOG_HIGHLIGHT_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from("highlight")),
+      highlight_theme_dark: std::env::var("BLOG_HIGHLIGHT_THEME_DARK")
+        .unwrap_or_else(|_| "Solarized (dark)".to_string()),
+      highlight_theme_light: std::env::var("BLOG_HIGHLIGHT_THEME_LIGHT")
+        .unwrap_or_else(|_| "Solarized (light)".to_string()),
+      image_cache_dir: std::env::var("BLOG_IMAGE_CACHE_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from("image-cache")),
+      image_widths: env_widths("BLOG_IMAGE_WIDTHS").unwrap_or_else(|| vec![400, 800, 1200, 1600]),
+      og_fonts: std::env::var("BLOG_OG_FONTS")
+        .map(|fonts| {
+          fonts
+            .split(',')
+            .map(str::trim)
+            .filter(|font| !font.is_empty())
+            .map(PathBuf::from)
+            .collect()
+        })
+        .unwrap_or_else(|_| default_og_fonts()),
+      sanitize_html: env_flag("BLOG_SANITIZE_HTML").unwrap_or(true),
+      iframe_hosts: std::env::var("BLOG_IFRAME_HOSTS")
+        .map(|hosts| {
+          hosts
+            .split(',')
+            .map(|host| host.trim().to_lowercase())
+            .filter(|host| !host.is_empty())
+            .collect()
+        })
+        .unwrap_or_else(|_| {
+          [
+            "www.youtube-nocookie.com",
+            "www.youtube.com",
+            "player.vimeo.com",
+            "codepen.io",
+          ]
+          .map(String::from)
+          .to_vec()
+        }),
+    }
+  }
+}
+
+fn env_flag(key: &str) -> Option<bool> {
+  let value = std::env::var(key).ok()?;
+  match value.to_lowercase().as_str() {
+    "1" | "true" | "yes" | "on" => Some(true),
+    "0" | "false" | "no" | "off" => Some(false),
+    _ => {
+      eprintln!("Ignoring {}={}: expected true or false", key, value);
+      None
+    }
+  }
+}
+
+fn env_timezone(key: &str) -> Option<Tz> {
+  let value = std::env::var(key).ok()?;
+  match value.parse() {
+    Ok(timezone) => Some(timezone),
+    Err(_) => {
+      eprintln!(
+        "Ignoring {}={}: expected an IANA timezone such as Asia/Seoul",
+        key, value
+      );
+      None
+    }
+  }
+}
+
+fn default_og_fonts() -> Vec<PathBuf> {
+  [
+    "/usr/share/fonts/truetype/dejavu/DejaVuSans-Bold.ttf",
+    "/usr/share/fonts/opentype/noto/NotoSansCJK-Bold.ttc",
+    "/usr/share/fonts/noto-cjk/NotoSansCJK-Bold.ttc",
+    "/usr/share/fonts/truetype/nanum/NanumGothicBold.ttf",
+    "/System/Library/Fonts/Supplemental/Arial Bold.ttf",
+    "/System/Library/Fonts/AppleSDGothicNeo.ttc",
+  ]
+  .iter()
+  .map(PathBuf::from)
+  .filter(|path| path.is_file())
+  .collect()
+}
+
+fn env_widths(key: &str) -> Option<Vec<u32>> {
+  let value = std::env::var(key).ok()?;
+  let widths: Result<Vec<u32>, _> = value
+    .split(',')
+    .map(|width| width.trim().parse::<u32>())
+    .collect();
+  match widths {
+    Ok(mut widths) if !widths.is_empty() && !widths.contains(&0) => {
+      widths.sort_unstable();
+      widths.dedup();
+      Some(widths)
+    }
+    _ => {
+      eprintln!(
+        "Ignoring {}={}: expected a comma-separated list of widths such as 400,800,1200",
+        key, value
+      );
+      None
+    }
+  }
+}
+
+pub fn site_config() -> &'static SiteConfig {
+  static CONFIG: LazyLock<SiteConfig> = LazyLock::new(SiteConfig::from_env);
+  &CONFIG
+}
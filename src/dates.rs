@@ -0,0 +1,165 @@
+// Post timestamps parsed from front matter
+//
+// Dates are stored as offset datetimes already converted to the site timezone, so the
+// client can format them without knowing that timezone.
+
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, TimeZone};
+use serde::{Deserialize, Deserializer};
+
+/// Local datetime layouts accepted without an offset
+const NAIVE_FORMATS: [&str; 4] = [
+  "%Y-%m-%dT%H:%M:%S%.f",
+  "%Y-%m-%dT%H:%M",
+  "%Y-%m-%d %H:%M:%S%.f",
+  "%Y-%m-%d %H:%M",
+];
+
+/// Parse `YYYY-MM-DD`, `YYYY-MM-DDTHH:MM[:SS]` or an RFC 3339 timestamp.
+///
+/// Forms without an offset are read as local time in the site timezone. The result is
+/// expressed in the site timezone either way.
+pub fn parse_datetime(value: &str) -> Option<DateTime<FixedOffset>> {
+  parse_datetime_in(value, &site_timezone())
+}
+
+/// [`parse_datetime`] with `timezone` as the site timezone
+fn parse_datetime_in<Tz: TimeZone>(value: &str, timezone: &Tz) -> Option<DateTime<FixedOffset>> {
+  let value = value.trim();
+
+  if let Ok(datetime) = DateTime::parse_from_rfc3339(value) {
+    return Some(datetime.with_timezone(timezone).fixed_offset());
+  }
+  let naive = NAIVE_FORMATS
+    .iter()
+    .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+    .or_else(|| {
+      NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+    })?;
+  // Times skipped by a DST jump don't exist; ambiguous ones take the earlier instant
+  timezone
+    .from_local_datetime(&naive)
+    .earliest()
+    .map(|datetime| datetime.fixed_offset())
+}
+
+#[cfg(feature = "ssr")]
+fn site_timezone() -> chrono_tz::Tz {
+  crate::config::site_config().timezone
+}
+
+#[cfg(not(feature = "ssr"))]
+fn site_timezone() -> chrono::Utc {
+  chrono::Utc
+}
+
+fn invalid<E: serde::de::Error>(value: &str) -> E {
+  E::custom(format!(
+    "invalid date `{}`, expected e.g. 2025-01-15, 2025-01-15T09:00 or 2025-01-15T09:00:00+09:00",
+    value
+  ))
+}
+
+/// Deserialize a required front matter date
+pub fn deserialize<'de, D>(deserializer: D) -> Result<DateTime<FixedOffset>, D::Error>
+where
+  D: Deserializer<'de>,
+{
+  let value = String::deserialize(deserializer)?;
+  parse_datetime(&value).ok_or_else(|| invalid(&value))
+}
+
+/// Deserialize an optional front matter date
+pub fn deserialize_option<'de, D>(
+  deserializer: D,
+) -> Result<Option<DateTime<FixedOffset>>, D::Error>
+where
+  D: Deserializer<'de>,
+{
+  match Option::<String>::deserialize(deserializer)? {
+    Some(value) => parse_datetime(&value)
+      .map(Some)
+      .ok_or_else(|| invalid(&value)),
+    None => Ok(None),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn parse(value: &str, offset_hours: i32) -> Option<String> {
+    let timezone = FixedOffset::east_opt(offset_hours * 3600).unwrap();
+    parse_datetime_in(value, &timezone).map(|datetime| datetime.to_rfc3339())
+  }
+
+  #[test]
+  fn local_forms_use_the_site_timezone() {
+    for (value, expected) in [
+      ("2025-01-15", "2025-01-15T00:00:00+09:00"),
+      (" 2025-01-15 ", "2025-01-15T00:00:00+09:00"),
+      ("2025-01-15T09:30", "2025-01-15T09:30:00+09:00"),
+      ("2025-01-15T09:30:15", "2025-01-15T09:30:15+09:00"),
+      ("2025-01-15 09:30", "2025-01-15T09:30:00+09:00"),
+      ("2025-01-15 09:30:15.5", "2025-01-15T09:30:15.500+09:00"),
+    ] {
+      assert_eq!(parse(value, 9).as_deref(), Some(expected), "{}", value);
+    }
+  }
+
+  #[test]
+  fn offsets_are_converted_to_the_site_timezone() {
+    for (value, expected) in [
+      ("2025-01-15T09:00:00+09:00", "2025-01-15T09:00:00+09:00"),
+      ("2025-01-15T00:00:00Z", "2025-01-15T09:00:00+09:00"),
+      ("2025-01-15T00:00:00z", "2025-01-15T09:00:00+09:00"),
+      ("2025-01-14T19:00:00-05:00", "2025-01-15T09:00:00+09:00"),
+    ] {
+      assert_eq!(parse(value, 9).as_deref(), Some(expected), "{}", value);
+    }
+    assert_eq!(
+      parse("2025-01-15T09:00:00+09:00", 0).as_deref(),
+      Some("2025-01-15T00:00:00+00:00")
+    );
+  }
+
+  #[test]
+  fn non_padded_dates() {
+    assert_eq!(parse("2025-1-5", 0).as_deref(), Some("2025-01-05T00:00:00+00:00"));
+    assert_eq!(parse("2025-1-5T9:05", 0).as_deref(), Some("2025-01-05T09:05:00+00:00"));
+  }
+
+  #[test]
+  fn garbage_is_rejected() {
+    for value in [
+      "",
+      "yesterday",
+      "2025-13-01",
+      "2025-02-30",
+      "2025-01-15T25:00",
+      "15/01/2025",
+      "2025-01-15T09:00:00+09",
+      "2025-01-15 extra",
+    ] {
+      assert_eq!(parse(value, 0), None, "{}", value);
+    }
+  }
+
+  #[cfg(feature = "ssr")]
+  #[test]
+  fn daylight_saving_gaps_and_overlaps() {
+    let new_york = chrono_tz::America::New_York;
+    let parse = |value: &str| parse_datetime_in(value, &new_york).map(|dt| dt.to_rfc3339());
+
+    assert_eq!(
+      parse("2025-07-01T12:00:00Z").as_deref(),
+      Some("2025-07-01T08:00:00-04:00")
+    );
+    assert_eq!(parse("2025-01-01").as_deref(), Some("2025-01-01T00:00:00-05:00"));
+    // 02:30 doesn't exist on the day clocks go forward
+    assert_eq!(parse("2025-03-09T02:30"), None);
+    // 01:30 happens twice on the day clocks go back
+    assert_eq!(parse("2025-11-02T01:30").as_deref(), Some("2025-11-02T01:30:00-04:00"));
+  }
+}
@@ -0,0 +1,10 @@
+// Escaping text for HTML and XML output
+
+/// Escape text for use in HTML or XML content and in double-quoted attribute values
+pub fn escape_html(text: &str) -> String {
+  text
+    .replace('&', "&amp;")
+    .replace('<', "&lt;")
+    .replace('>', "&gt;")
+    .replace('"', "&quot;")
+}
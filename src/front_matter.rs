@@ -0,0 +1,335 @@
+// Front matter extraction for markdown content files
+//
+// Supports YAML blocks delimited by `---` and TOML blocks delimited by `+++`,
+// detected from the opening delimiter of each file.
+
+use serde::de::DeserializeOwned;
+
+/// Serialization format of a front matter block
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrontMatterFormat {
+  /// `---` delimited YAML (Jekyll/Hugo style)
+  Yaml,
+  /// `+++` delimited TOML (Zola/Hugo style)
+  Toml,
+}
+
+impl FrontMatterFormat {
+  fn from_opening(line: &str) -> Option<Self> {
+    match line.trim_end() {
+      "---" => Some(FrontMatterFormat::Yaml),
+      "+++" => Some(FrontMatterFormat::Toml),
+      _ => None,
+    }
+  }
+
+  fn is_closing(self, line: &str) -> bool {
+    let line = line.trim_end();
+    match self {
+      FrontMatterFormat::Yaml => line == "---" || line == "...",
+      FrontMatterFormat::Toml => line == "+++",
+    }
+  }
+}
+
+/// Front matter block split off the top of a markdown file
+#[derive(Debug, Clone, Copy)]
+pub struct FrontMatter<'a> {
+  pub format: FrontMatterFormat,
+  /// Raw text between the delimiters
+  pub raw: &'a str,
+  /// 1-based line of the first front matter line in the file
+  pub start_line: usize,
+  /// Markdown following the closing delimiter
+  pub body: &'a str,
+  /// 1-based line of the first body line in the file
+  pub body_line: usize,
+}
+
+/// Why a front matter block could not be extracted or deserialized
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FrontMatterError {
+  /// The file does not open with a `---` or `+++` line
+  Missing,
+  /// The opening delimiter on `line` is never closed
+  Unterminated { line: usize },
+  /// The block is malformed or does not match the expected structure
+  Invalid {
+    line: Option<usize>,
+    column: Option<usize>,
+    field: Option<String>,
+    message: String,
+  },
+}
+
+/// Split a leading front matter block from the markdown body.
+///
+/// Only a block that opens on the very first line is recognized, and it is closed by the
+/// next line consisting solely of the same delimiter, so horizontal rules and dashes inside
+/// values later in the file are left alone.
+pub fn split(source: &str) -> Result<FrontMatter<'_>, FrontMatterError> {
+  let source = source.strip_prefix('\u{feff}').unwrap_or(source);
+  let mut lines = source.split_inclusive('\n');
+
+  let opening = lines.next().unwrap_or("");
+  let format = FrontMatterFormat::from_opening(opening).ok_or(FrontMatterError::Missing)?;
+
+  let start = opening.len();
+  let mut offset = start;
+  for (index, line) in lines.enumerate() {
+    if format.is_closing(line) {
+      return Ok(FrontMatter {
+        format,
+        raw: &source[start..offset],
+        start_line: 2,
+        body: &source[offset + line.len()..],
+        body_line: index + 3,
+      });
+    }
+    offset += line.len();
+  }
+
+  Err(FrontMatterError::Unterminated { line: 1 })
+}
+
+/// Deserialize a front matter block, keeping track of where and in which field it failed
+pub fn parse<T: DeserializeOwned>(front_matter: &FrontMatter) -> Result<T, FrontMatterError> {
+  match front_matter.format {
+    FrontMatterFormat::Yaml => parse_yaml(front_matter),
+    FrontMatterFormat::Toml => parse_toml(front_matter),
+  }
+}
+
+fn parse_yaml<T: DeserializeOwned>(front_matter: &FrontMatter) -> Result<T, FrontMatterError> {
+  let deserializer = serde_yaml::Deserializer::from_str(front_matter.raw);
+  serde_path_to_error::deserialize(deserializer).map_err(|error| {
+    let field = error.path().to_string();
+    let inner = error.into_inner();
+    let location = inner.location();
+    let mut message = inner.to_string();
+    if let Some(location) = &location {
+      let suffix = format!(" at line {} column {}", location.line(), location.column());
+      if let Some(stripped) = message.strip_suffix(&suffix) {
+        message = stripped.to_string();
+      }
+    }
+
+    invalid(
+      front_matter,
+      location.map(|location| (location.line(), location.column())),
+      &field,
+      message,
+    )
+  })
+}
+
+fn parse_toml<T: DeserializeOwned>(front_matter: &FrontMatter) -> Result<T, FrontMatterError> {
+  let mut table: toml::Table = toml::from_str(front_matter.raw).map_err(|error| {
+    let location = error
+      .span()
+      .map(|span| line_column(front_matter.raw, span.start));
+    invalid(front_matter, location, "", error.message().to_string())
+  })?;
+
+  // TOML has native datetimes while our metadata stores dates as strings
+  for (_, value) in table.iter_mut() {
+    stringify_datetimes(value);
+  }
+
+  serde_path_to_error::deserialize(toml::Value::Table(table)).map_err(|error| {
+    let field = error.path().to_string();
+    let message = error.into_inner().message().to_string();
+    // Deserializing from a parsed table loses spans, so point at the key instead
+    let top_level_key = offending_field(&field, &message)
+      .and_then(|field| field.split(['.', '[']).next().map(str::to_string));
+    let location = top_level_key.and_then(|key| find_toml_key(front_matter.raw, &key));
+    invalid(front_matter, location, &field, message)
+  })
+}
+
+fn stringify_datetimes(value: &mut toml::Value) {
+  match value {
+    toml::Value::Datetime(datetime) => *value = toml::Value::String(datetime.to_string()),
+    toml::Value::Array(values) => values.iter_mut().for_each(stringify_datetimes),
+    toml::Value::Table(table) => table
+      .iter_mut()
+      .for_each(|(_, value)| stringify_datetimes(value)),
+    _ => {}
+  }
+}
+
+/// 1-based line and column of the `key = ...` assignment in a TOML block
+fn find_toml_key(raw: &str, key: &str) -> Option<(usize, usize)> {
+  raw.lines().enumerate().find_map(|(index, line)| {
+    let trimmed = line.trim_start();
+    let rest = trimmed
+      .strip_prefix(key)
+      .or_else(|| trimmed.strip_prefix(&format!("\"{}\"", key)))?;
+    rest
+      .trim_start()
+      .starts_with('=')
+      .then(|| (index + 1, line.len() - trimmed.len() + 1))
+  })
+}
+
+/// 1-based line and column of a byte offset
+fn line_column(text: &str, offset: usize) -> (usize, usize) {
+  let before = &text[..offset.min(text.len())];
+  let line = before.matches('\n').count() + 1;
+  let column = before.rsplit('\n').next().unwrap_or("").chars().count() + 1;
+  (line, column)
+}
+
+fn invalid(
+  front_matter: &FrontMatter,
+  location: Option<(usize, usize)>,
+  path: &str,
+  mut message: String,
+) -> FrontMatterError {
+  let field = offending_field(path, &message);
+  if let Some(stripped) = field
+    .as_ref()
+    .and_then(|field| message.strip_prefix(&format!("{}: ", field)))
+  {
+    message = stripped.to_string();
+  }
+
+  FrontMatterError::Invalid {
+    line: location.map(|(line, _)| front_matter.start_line + line - 1),
+    column: location.map(|(_, column)| column),
+    field,
+    message,
+  }
+}
+
+/// Name the front matter field an error refers to, if it can be determined
+fn offending_field(path: &str, message: &str) -> Option<String> {
+  if !path.is_empty() && path != "." {
+    return Some(path.to_string());
+  }
+  ["missing field `", "unknown field `", "duplicate field `"]
+    .iter()
+    .find_map(|prefix| message.split(prefix).nth(1))
+    .and_then(|rest| rest.split('`').next())
+    .map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use serde::Deserialize;
+
+  #[derive(Debug, Deserialize, PartialEq)]
+  struct Metadata {
+    title: String,
+    #[serde(default)]
+    tags: Vec<String>,
+  }
+
+  fn hello() -> Metadata {
+    Metadata {
+      title: "Hello".to_string(),
+      tags: vec!["a".to_string(), "b".to_string()],
+    }
+  }
+
+  #[test]
+  fn split_yaml_block() {
+    let front_matter = split("---\ntitle: Hello\n---\n\nBody text\n").unwrap();
+    assert_eq!(front_matter.format, FrontMatterFormat::Yaml);
+    assert_eq!(front_matter.raw, "title: Hello\n");
+    assert_eq!(front_matter.start_line, 2);
+    assert_eq!(front_matter.body, "\nBody text\n");
+    assert_eq!(front_matter.body_line, 4);
+  }
+
+  #[test]
+  fn split_toml_block() {
+    let front_matter = split("+++\ntitle = \"Hello\"\n+++\nBody\n").unwrap();
+    assert_eq!(front_matter.format, FrontMatterFormat::Toml);
+    assert_eq!(front_matter.raw, "title = \"Hello\"\n");
+    assert_eq!(front_matter.body, "Body\n");
+    assert_eq!(front_matter.body_line, 4);
+  }
+
+  #[test]
+  fn split_closes_with_the_opening_delimiter() {
+    assert_eq!(
+      split("---\ntitle: Hello\n+++\n").unwrap_err(),
+      FrontMatterError::Unterminated { line: 1 }
+    );
+    let front_matter = split("+++\ntitle = \"Hello\"\n---\n+++\n").unwrap();
+    assert_eq!(front_matter.raw, "title = \"Hello\"\n---\n");
+  }
+
+  #[test]
+  fn split_ignores_byte_order_mark() {
+    let front_matter = split("\u{feff}---\ntitle: Hello\n---\n").unwrap();
+    assert_eq!(front_matter.raw, "title: Hello\n");
+    assert_eq!(front_matter.body, "");
+  }
+
+  #[test]
+  fn split_leaves_later_rules_in_the_body() {
+    let front_matter = split("---\ntitle: Hello\n---\nBefore\n---\nAfter\n").unwrap();
+    assert_eq!(front_matter.body, "Before\n---\nAfter\n");
+  }
+
+  #[test]
+  fn split_requires_a_block_on_the_first_line() {
+    assert_eq!(split("# Title\n").unwrap_err(), FrontMatterError::Missing);
+    assert_eq!(split("\n---\ntitle: Hello\n---\n").unwrap_err(), FrontMatterError::Missing);
+  }
+
+  #[test]
+  fn split_reports_unterminated_block() {
+    assert_eq!(
+      split("---\ntitle: Hello\n").unwrap_err(),
+      FrontMatterError::Unterminated { line: 1 }
+    );
+  }
+
+  #[test]
+  fn parse_yaml() {
+    let front_matter = split("---\ntitle: Hello\ntags: [a, b]\n---\n").unwrap();
+    assert_eq!(parse::<Metadata>(&front_matter).unwrap(), hello());
+  }
+
+  #[test]
+  fn parse_toml() {
+    let front_matter = split("+++\ntitle = \"Hello\"\ntags = [\"a\", \"b\"]\n+++\n").unwrap();
+    assert_eq!(parse::<Metadata>(&front_matter).unwrap(), hello());
+  }
+
+  #[test]
+  fn parse_toml_reports_field_and_file_line() {
+    let front_matter = split("+++\ntitle = \"Hello\"\ntags = \"rust\"\n+++\n").unwrap();
+    let error = parse::<Metadata>(&front_matter).unwrap_err();
+    let FrontMatterError::Invalid { line, field, .. } = error else {
+      panic!("expected an invalid front matter error, got {:?}", error);
+    };
+    assert_eq!(field.as_deref(), Some("tags"));
+    assert_eq!(line, Some(3));
+  }
+
+  #[test]
+  fn parse_reports_field_and_file_line() {
+    let error = parse::<Metadata>(&split("---\ntitle: Hello\ntags: rust\n---\n").unwrap())
+      .unwrap_err();
+    let FrontMatterError::Invalid { line, field, .. } = error else {
+      panic!("expected an invalid front matter error, got {:?}", error);
+    };
+    assert_eq!(field.as_deref(), Some("tags"));
+    assert_eq!(line, Some(3));
+  }
+
+  #[test]
+  fn parse_reports_missing_field() {
+    let error = parse::<Metadata>(&split("---\ntags: []\n---\n").unwrap()).unwrap_err();
+    assert!(
+      matches!(&error, FrontMatterError::Invalid { message, .. } if message.contains("title")),
+      "{:?}",
+      error
+    );
+  }
+}
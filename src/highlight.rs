@@ -0,0 +1,481 @@
+//! Syntax-highlighted code blocks, with the options a fence info string can carry:
+//!
+//! ````markdown
+//! ```rust title="main.rs" {3,5-7} linenos
+//! ```
+//! ````
+//!
+//! `diff` (or a `diff-<lang>` language) colors `+`/`-` lines while the rest of each
+//! line is still highlighted as `<lang>`.
+//!
+//! Blocks carry `hl-*` classes instead of inline colors; [`stylesheet`] turns the dark
+//! and light themes into CSS, served at `/highlight.css`.
+//!
+//! Extra syntaxes and themes are loaded from the highlight directory (`highlight/` by
+//! default) on top of syntect's built-in ones.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+use syntect::highlighting::{Theme, ThemeSet};
+use syntect::html::{css_for_theme_with_class_style, line_tokens_to_classed_spans, ClassStyle};
+use syntect::parsing::{ParseState, ScopeStack, SyntaxDefinition, SyntaxSet};
+use syntect::util::LinesWithEndings;
+
+use crate::escape::escape_html;
+
+const DEFAULT_DARK_THEME: &str = "Solarized (dark)";
+const DEFAULT_LIGHT_THEME: &str = "Solarized (light)";
+
+/// Prefix for the scope classes so they can't clash with the site's own styles
+const CLASS_STYLE: ClassStyle = ClassStyle::SpacedPrefixed { prefix: "hl-" };
+
+/// Syntaxes and the configured themes
+struct Highlighting {
+  syntaxes: SyntaxSet,
+  dark: Theme,
+  light: Theme,
+}
+
+// Loading these takes a noticeable amount of time, so it happens once per process
+static HIGHLIGHTING: LazyLock<Highlighting> = LazyLock::new(Highlighting::load);
+
+impl Highlighting {
+  fn load() -> Self {
+    let config = crate::config::site_config();
+    let dir = &config.highlight_dir;
+
+    let mut syntaxes = SyntaxSet::load_defaults_newlines().into_builder();
+    for path in files_with_extension(dir, "sublime-syntax") {
+      let name = path.file_stem().and_then(|stem| stem.to_str());
+      let syntax = fs::read_to_string(&path)
+        .map_err(|e| e.to_string())
+        .and_then(|source| {
+          SyntaxDefinition::load_from_str(&source, true, name).map_err(|e| e.to_string())
+        });
+      match syntax {
+        Ok(syntax) => syntaxes.add(syntax),
+        Err(e) => eprintln!("Error loading syntax {}: {}", path.display(), e),
+      }
+    }
+
+    // Themes from disk are named after their file, e.g. `highlight/Nord.tmTheme` is "Nord"
+    let mut themes = ThemeSet::load_defaults();
+    for path in files_with_extension(dir, "tmTheme") {
+      let Some(name) = path.file_stem().and_then(|stem| stem.to_str()) else {
+        continue;
+      };
+      match ThemeSet::get_theme(&path) {
+        Ok(theme) => {
+          themes.themes.insert(name.to_string(), theme);
+        }
+        Err(e) => eprintln!("Error loading theme {}: {}", path.display(), e),
+      }
+    }
+
+    Highlighting {
+      syntaxes: syntaxes.build(),
+      dark: select_theme(
+        &themes,
+        &config.highlight_theme_dark,
+        "BLOG_HIGHLIGHT_THEME_DARK",
+        DEFAULT_DARK_THEME,
+      ),
+      light: select_theme(
+        &themes,
+        &config.highlight_theme_light,
+        "BLOG_HIGHLIGHT_THEME_LIGHT",
+        DEFAULT_LIGHT_THEME,
+      ),
+    }
+  }
+}
+
+/// Look up a configured theme, listing the available ones when the name is wrong
+fn select_theme(themes: &ThemeSet, name: &str, key: &str, default: &str) -> Theme {
+  if let Some(theme) = themes.themes.get(name) {
+    return theme.clone();
+  }
+
+  let available: Vec<String> = themes
+    .themes
+    .keys()
+    .map(|name| format!("{:?}", name))
+    .collect();
+  eprintln!(
+    "Unknown highlight theme {}={:?}, using {:?} instead. Available themes: {}",
+    key,
+    name,
+    default,
+    available.join(", ")
+  );
+  themes.themes[default].clone()
+}
+
+/// Files with `extension` anywhere under `dir`, in a stable order
+fn files_with_extension(dir: &Path, extension: &str) -> Vec<PathBuf> {
+  let Ok(entries) = fs::read_dir(dir) else {
+    return Vec::new();
+  };
+  let mut paths: Vec<PathBuf> = entries.flatten().map(|entry| entry.path()).collect();
+  paths.sort();
+
+  let mut files = Vec::new();
+  for path in paths {
+    if path.is_dir() {
+      files.extend(files_with_extension(&path, extension));
+    } else if path.extension().and_then(|ext| ext.to_str()) == Some(extension) {
+      files.push(path);
+    }
+  }
+  files
+}
+
+/// Load syntaxes and themes now, so problems with them are reported at startup
+pub fn init() {
+  LazyLock::force(&HIGHLIGHTING);
+}
+
+/// Options parsed from a fence info string
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct FenceInfo {
+  /// Language token used to pick the syntax, without any `diff-` prefix
+  pub lang: String,
+  pub title: Option<String>,
+  /// 1-based lines to emphasize, from `{3,5-7}`
+  pub highlight: Vec<(usize, usize)>,
+  pub line_numbers: bool,
+  pub diff: bool,
+}
+
+impl FenceInfo {
+  pub fn parse(info: &str) -> Self {
+    let mut fence = FenceInfo::default();
+
+    for token in split_info(info) {
+      if let Some(ranges) = token.strip_prefix('{').and_then(|t| t.strip_suffix('}')) {
+        fence
+          .highlight
+          .extend(ranges.split(',').filter_map(parse_range));
+      } else if let Some((key, value)) = token.split_once('=') {
+        if value.starts_with('"') && (value.len() == 1 || !value.ends_with('"')) {
+          eprintln!("Ignoring unterminated {} in code block info {:?}", key, info);
+          continue;
+        }
+        let value = value.trim_matches('"');
+        match key {
+          "title" => fence.title = Some(value.to_string()),
+          "hl_lines" => fence
+            .highlight
+            .extend(value.split([',', ' ']).filter_map(parse_range)),
+          _ => {}
+        }
+      } else if token == "linenos" {
+        fence.line_numbers = true;
+      } else if token == "diff" {
+        fence.diff = true;
+      } else if fence.lang.is_empty() {
+        match token.strip_prefix("diff-") {
+          Some(lang) => {
+            fence.diff = true;
+            fence.lang = lang.to_string();
+          }
+          None => fence.lang = token,
+        }
+      }
+    }
+
+    fence
+  }
+
+  fn is_highlighted(&self, line: usize) -> bool {
+    self
+      .highlight
+      .iter()
+      .any(|&(start, end)| (start..=end).contains(&line))
+  }
+}
+
+/// Whitespace-separated tokens, keeping `title="two words"` and `{3, 5}` together
+fn split_info(info: &str) -> Vec<String> {
+  let mut tokens = Vec::new();
+  let mut current = String::new();
+  let mut in_quotes = false;
+  let mut in_braces = false;
+
+  for c in info.chars() {
+    match c {
+      '"' => {
+        in_quotes = !in_quotes;
+        current.push(c);
+      }
+      '{' | '}' if !in_quotes => {
+        in_braces = c == '{';
+        current.push(c);
+      }
+      c if c.is_whitespace() && in_braces => {}
+      c if c.is_whitespace() && !in_quotes => {
+        if !current.is_empty() {
+          tokens.push(std::mem::take(&mut current));
+        }
+      }
+      // `rust,linenos` is common in other generators
+      ',' if !in_quotes && !in_braces => {
+        if !current.is_empty() {
+          tokens.push(std::mem::take(&mut current));
+        }
+      }
+      c => current.push(c),
+    }
+  }
+  if !current.is_empty() {
+    tokens.push(current);
+  }
+  tokens
+}
+
+fn parse_range(range: &str) -> Option<(usize, usize)> {
+  let range = range.trim();
+  match range.split_once('-') {
+    Some((start, end)) => Some((start.trim().parse().ok()?, end.trim().parse().ok()?)),
+    None => range.parse().ok().map(|line| (line, line)),
+  }
+}
+
+/// Render a fenced code block as class-highlighted HTML
+pub fn render_code_block(code: &str, info: &FenceInfo) -> String {
+  let ss = &HIGHLIGHTING.syntaxes;
+  let known_syntax = ss.find_syntax_by_token(&info.lang);
+  let syntax = known_syntax.unwrap_or_else(|| ss.find_syntax_plain_text());
+  let mut parse_state = ParseState::new(syntax);
+  let mut scope_stack = ScopeStack::new();
+  // Scope spans still open at the end of the previous line, reopened inside the next one
+  let mut open_spans: Vec<String> = Vec::new();
+
+  let mut html = format!(
+    r#"<pre class="highlight{}"><code>"#,
+    if info.line_numbers { " linenos" } else { "" }
+  );
+
+  for (index, line) in LinesWithEndings::from(code).enumerate() {
+    let (marker, text) = match line.chars().next() {
+      Some(marker @ ('+' | '-' | ' ')) if info.diff => (Some(marker), &line[1..]),
+      _ => (None, line),
+    };
+
+    let mut classes = String::from("code-line");
+    if info.is_highlighted(index + 1) {
+      classes.push_str(" highlighted");
+    }
+    match marker {
+      Some('+') => classes.push_str(" diff-add"),
+      Some('-') => classes.push_str(" diff-remove"),
+      _ => {}
+    }
+
+    html.push_str(&format!(r#"<span class="{}">"#, classes));
+    if let Some(marker) = marker {
+      html.push_str(&format!(
+        r#"<span class="diff-marker" aria-hidden="true">{}</span>"#,
+        marker
+      ));
+    }
+    html.extend(open_spans.iter().map(String::as_str));
+
+    let spans = parse_state
+      .parse_line(text, ss)
+      .ok()
+      .and_then(|ops| line_tokens_to_classed_spans(text, &ops, CLASS_STYLE, &mut scope_stack).ok())
+      .map(|(spans, _)| spans);
+    match spans {
+      Some(spans) => {
+        // The newline stays outside the line's span so emphasized lines don't wrap onto the next
+        let spans = spans.replace('\n', "");
+        track_open_spans(&spans, &mut open_spans);
+        html.push_str(&spans);
+      }
+      None => html.push_str(&escape_html(text.trim_end_matches('\n'))),
+    }
+
+    html.push_str(&"</span>".repeat(open_spans.len()));
+    html.push_str("</span>\n");
+  }
+
+  html.push_str("</code></pre>");
+
+  let title = info
+    .title
+    .as_deref()
+    .map(|title| format!(r#"<div class="code-title">{}</div>"#, escape_html(title)));
+  // Shown as a label by the client; plain text gets none
+  let lang = known_syntax
+    .map(|syntax| format!(r#" data-lang="{}""#, escape_html(&syntax.name)))
+    .unwrap_or_default();
+  format!(
+    r#"<div class="code-block"{}>{}{}</div>"#,
+    lang,
+    title.unwrap_or_default(),
+    html
+  )
+}
+
+/// Follow the `<span ...>`/`</span>` tags syntect emitted, keeping the ones left open
+fn track_open_spans(html: &str, open_spans: &mut Vec<String>) {
+  let mut rest = html;
+  while let Some(start) = rest.find('<') {
+    let Some(end) = rest[start..].find('>') else {
+      break;
+    };
+    let tag = &rest[start..start + end + 1];
+    if tag == "</span>" {
+      open_spans.pop();
+    } else {
+      open_spans.push(tag.to_string());
+    }
+    rest = &rest[start + end + 1..];
+  }
+}
+
+/// CSS for the dark and light themes; the light rules apply when the site is in light mode
+pub fn stylesheet() -> &'static str {
+  static STYLESHEET: LazyLock<String> = LazyLock::new(|| {
+    let dark = theme_css(
+      &HIGHLIGHTING.dark,
+      &["html:not(.light-mode) body:not(.light-mode) pre.highlight"],
+    );
+    let light = theme_css(
+      &HIGHLIGHTING.light,
+      &[
+        "html.light-mode pre.highlight",
+        "body.light-mode pre.highlight",
+      ],
+    );
+    format!("{}\n{}", dark, light)
+  });
+  &STYLESHEET
+}
+
+/// syntect's CSS for a theme, with every selector nested under `scopes`
+fn theme_css(theme: &Theme, scopes: &[&str]) -> String {
+  let name = theme.name.as_deref().unwrap_or("unnamed theme");
+  let css = match css_for_theme_with_class_style(theme, CLASS_STYLE) {
+    Ok(css) => css,
+    Err(e) => {
+      eprintln!(
+        "Could not generate CSS for highlight theme {:?}: {}",
+        name, e
+      );
+      return String::new();
+    }
+  };
+
+  let mut scoped = format!("/* {} */\n", name);
+  let css = strip_comments(&css);
+  for rule in css.split_inclusive('}') {
+    let Some((selectors, body)) = rule.split_once('{') else {
+      continue;
+    };
+    let selectors: Vec<String> = selectors
+      .split(',')
+      .map(str::trim)
+      .filter(|selector| !selector.is_empty())
+      .flat_map(|selector| {
+        scopes.iter().map(move |scope| match selector {
+          // The theme's base colors belong on the <pre> itself, which has no `hl-code` class
+          ".hl-code" => scope.to_string(),
+          _ => format!("{} {}", scope, selector),
+        })
+      })
+      .collect();
+    if !selectors.is_empty() {
+      scoped.push_str(&format!("{} {{{}\n", selectors.join(", "), body.trim_end()));
+    }
+  }
+  scoped
+}
+
+fn strip_comments(css: &str) -> String {
+  let mut stripped = String::new();
+  let mut rest = css;
+  while let Some(start) = rest.find("/*") {
+    stripped.push_str(&rest[..start]);
+    rest = match rest[start..].find("*/") {
+      Some(end) => &rest[start + end + 2..],
+      None => "",
+    };
+  }
+  stripped.push_str(rest);
+  stripped
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn language_only() {
+    assert_eq!(
+      FenceInfo::parse("rust"),
+      FenceInfo {
+        lang: "rust".to_string(),
+        ..FenceInfo::default()
+      }
+    );
+    assert_eq!(FenceInfo::parse(""), FenceInfo::default());
+  }
+
+  #[test]
+  fn title() {
+    let fence = FenceInfo::parse(r#"rust title="src/main.rs""#);
+    assert_eq!(fence.lang, "rust");
+    assert_eq!(fence.title.as_deref(), Some("src/main.rs"));
+
+    let fence = FenceInfo::parse(r#"toml title="Cargo manifest" linenos"#);
+    assert_eq!(fence.title.as_deref(), Some("Cargo manifest"));
+    assert!(fence.line_numbers);
+  }
+
+  #[test]
+  fn unterminated_title_is_ignored() {
+    let fence = FenceInfo::parse(r#"rust title="main.rs"#);
+    assert_eq!(fence.lang, "rust");
+    assert_eq!(fence.title, None);
+    assert_eq!(FenceInfo::parse(r#"rust title=""#).title, None);
+  }
+
+  #[test]
+  fn ranges() {
+    for info in [
+      "rust {3,5-7}",
+      "rust {3, 5-7}",
+      "rust { 3 , 5 - 7 }",
+      r#"rust hl_lines="3 5-7""#,
+    ] {
+      assert_eq!(FenceInfo::parse(info).highlight, [(3, 3), (5, 7)], "{}", info);
+    }
+    let fence = FenceInfo::parse("{1,4} python");
+    assert_eq!(fence.lang, "python");
+    assert_eq!(fence.highlight, [(1, 1), (4, 4)]);
+    assert!(fence.is_highlighted(4));
+    assert!(!fence.is_highlighted(2));
+  }
+
+  #[test]
+  fn line_numbers() {
+    assert!(FenceInfo::parse("rust linenos").line_numbers);
+    assert!(FenceInfo::parse("rust,linenos").line_numbers);
+    assert!(!FenceInfo::parse("rust").line_numbers);
+  }
+
+  #[test]
+  fn diff() {
+    let fence = FenceInfo::parse("diff-rust {2}");
+    assert_eq!(fence.lang, "rust");
+    assert!(fence.diff);
+    assert_eq!(fence.highlight, [(2, 2)]);
+
+    let fence = FenceInfo::parse("diff");
+    assert_eq!(fence.lang, "");
+    assert!(fence.diff);
+  }
+}
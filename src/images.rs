@@ -0,0 +1,353 @@
+// Responsive images: resized and WebP variants of local post images, rendered on first
+// request and cached on disk
+
+use serde::{Deserialize, Serialize};
+
+/// URL prefix the cached image variants are served under
+pub const CACHE_URL: &str = "/_images/";
+
+/// `sizes` for images in post content, which is at most 768px wide
+pub const CONTENT_SIZES: &str = "(max-width: 800px) 100vw, 768px";
+
+/// An image with optional resized variants and intrinsic dimensions
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ResponsiveImage {
+  pub src: String,
+  pub width: Option<u32>,
+  pub height: Option<u32>,
+  /// Variants in the original format, e.g. `/_images/1f..-400.jpg 400w, ...`
+  pub srcset: Option<String>,
+  /// Lossless WebP variants, for PNG originals
+  pub webp_srcset: Option<String>,
+  /// Size of the original file in bytes, for feed enclosures
+  pub bytes: Option<u64>,
+}
+
+impl ResponsiveImage {
+  /// An image we know nothing about beyond its URL (remote, SVG, or failed to process)
+  pub fn plain(src: String) -> Self {
+    ResponsiveImage {
+      src,
+      width: None,
+      height: None,
+      srcset: None,
+      webp_srcset: None,
+      bytes: None,
+    }
+  }
+
+  /// `<picture>` markup for post content
+  #[cfg(feature = "ssr")]
+  pub fn to_html(&self, alt: &str, title: &str) -> String {
+    use crate::escape::escape_html;
+
+    let mut html = String::from("<picture>");
+    if let Some(webp_srcset) = &self.webp_srcset {
+      html.push_str(&format!(
+        r#"<source type="image/webp" srcset="{}" sizes="{}">"#,
+        escape_html(webp_srcset),
+        CONTENT_SIZES
+      ));
+    }
+    html.push_str(&format!(r#"<img src="{}""#, escape_html(&self.src)));
+    if let Some(srcset) = &self.srcset {
+      html.push_str(&format!(
+        r#" srcset="{}" sizes="{}""#,
+        escape_html(srcset),
+        CONTENT_SIZES
+      ));
+    }
+    if let (Some(width), Some(height)) = (self.width, self.height) {
+      html.push_str(&format!(r#" width="{}" height="{}""#, width, height));
+    }
+    html.push_str(&format!(r#" alt="{}""#, escape_html(alt)));
+    if !title.is_empty() {
+      html.push_str(&format!(r#" title="{}""#, escape_html(title)));
+    }
+    html.push_str(r#" loading="lazy" decoding="async"></picture>"#);
+    html
+  }
+}
+
+/// Responsive versions of `url` if it's a local image, otherwise just the URL
+pub(crate) fn resolve(url: String, bundle: Option<&crate::posts::BundleAssets>) -> ResponsiveImage {
+  #[cfg(feature = "ssr")]
+  if let Some(image) = responsive_image(&url, bundle) {
+    return image;
+  }
+  #[cfg(not(feature = "ssr"))]
+  let _ = bundle;
+  ResponsiveImage::plain(url)
+}
+
+/// Resize a local image to the configured widths, writing missing variants to the cache
+///
+/// Returns `None` for remote URLs, formats we don't resize (SVG, GIF) and images that
+/// fail to decode, which are then left as plain `<img>` tags.
+#[cfg(feature = "ssr")]
+pub(crate) fn responsive_image(
+  url: &str,
+  bundle: Option<&crate::posts::BundleAssets>,
+) -> Option<ResponsiveImage> {
+  use std::collections::HashMap;
+  use std::sync::{LazyLock, Mutex};
+
+  // The thumbnail and the post body usually point at the same image
+  type Stamp = Option<(Option<std::time::SystemTime>, u64)>;
+  type Processed = HashMap<(String, std::path::PathBuf), (Stamp, Option<ResponsiveImage>)>;
+  static PROCESSED: LazyLock<Mutex<Processed>> = LazyLock::new(Default::default);
+
+  let path = local_path(url, bundle)?;
+  let format = image::ImageFormat::from_path(&path).ok()?;
+  if !matches!(
+    format,
+    image::ImageFormat::Png | image::ImageFormat::Jpeg | image::ImageFormat::WebP
+  ) {
+    return None;
+  }
+
+  // Reprocess when the file changes
+  let stamp = std::fs::metadata(&path)
+    .ok()
+    .map(|metadata| (metadata.modified().ok(), metadata.len()));
+  let key = (url.to_string(), path.clone());
+  if let Some((cached_stamp, image)) = PROCESSED.lock().ok()?.get(&key) {
+    if *cached_stamp == stamp {
+      return image.clone();
+    }
+  }
+
+  let image = match generate_variants(url, &path, format) {
+    Ok(image) => Some(image),
+    Err(e) => {
+      eprintln!("Error processing image {}: {}", path.display(), e);
+      None
+    }
+  };
+  if let Ok(mut processed) = PROCESSED.lock() {
+    processed.insert(key, (stamp, image.clone()));
+  }
+  image
+}
+
+/// The file behind an image URL: a page bundle file or a static file from the site root
+#[cfg(feature = "ssr")]
+pub(crate) fn local_path(
+  url: &str,
+  bundle: Option<&crate::posts::BundleAssets>,
+) -> Option<std::path::PathBuf> {
+  use crate::posts::is_safe_relative_path;
+
+  let url = url.split(['?', '#']).next().unwrap_or_default();
+  let path = match bundle.and_then(|bundle| Some((bundle, url.strip_prefix(bundle.url)?))) {
+    Some((bundle, file)) => {
+      if !is_safe_relative_path(file) {
+        return None;
+      }
+      bundle.dir.join(file)
+    }
+    None => {
+      let file = url
+        .strip_prefix('/')
+        .filter(|file| !file.starts_with('/'))?;
+      if !is_safe_relative_path(file) {
+        return None;
+      }
+      // `public/` is copied to the site root by cargo-leptos; look there too when running
+      // the server binary directly
+      let site_root = std::env::var("LEPTOS_SITE_ROOT").unwrap_or_else(|_| "target/site".into());
+      [site_root.as_str(), "public"]
+        .iter()
+        .map(|root| std::path::Path::new(root).join(file))
+        .find(|path| path.is_file())?
+    }
+  };
+  path.is_file().then_some(path)
+}
+
+/// A resized or WebP copy of a local image, rendered when it's first requested
+#[cfg(feature = "ssr")]
+#[derive(Debug, Clone)]
+struct Variant {
+  source: std::path::PathBuf,
+  /// Hash of the source file the variant was planned for
+  hash: String,
+  width: u32,
+  format: image::ImageFormat,
+}
+
+/// Variants listed on pages so far, by file name under [`CACHE_URL`]
+#[cfg(feature = "ssr")]
+static VARIANTS: std::sync::LazyLock<
+  std::sync::Mutex<std::collections::HashMap<String, Variant>>,
+> = std::sync::LazyLock::new(Default::default);
+
+/// Work out the variants of an image from its header, without decoding it
+#[cfg(feature = "ssr")]
+fn generate_variants(
+  url: &str,
+  path: &std::path::Path,
+  format: image::ImageFormat,
+) -> image::ImageResult<ResponsiveImage> {
+  use image::{ImageFormat, ImageReader};
+
+  let config = crate::config::site_config();
+  let bytes = std::fs::read(path)?;
+  let hash = format!("{:016x}", stable_hash(&[&bytes]));
+  let (width, height) =
+    ImageReader::with_format(std::io::Cursor::new(&bytes), format).into_dimensions()?;
+
+  let variant = |target: u32, format: ImageFormat| -> (u32, String) {
+    let name = format!("{}-{}.{}", hash, target, format.extensions_str()[0]);
+    if let Ok(mut variants) = VARIANTS.lock() {
+      let variant = Variant {
+        source: path.to_path_buf(),
+        hash: hash.clone(),
+        width: target,
+        format,
+      };
+      variants.insert(name.clone(), variant);
+    }
+    (target, format!("{}{}", CACHE_URL, name))
+  };
+
+  let widths: Vec<u32> = config
+    .image_widths
+    .iter()
+    .copied()
+    .filter(|&target| target < width)
+    .collect();
+
+  let mut srcset: Vec<(u32, String)> = widths
+    .iter()
+    .map(|&target| variant(target, format))
+    .collect();
+  srcset.push((width, url.to_string()));
+
+  // image's WebP encoder is lossless, which beats PNG but rarely JPEG photos
+  let webp_srcset = (format == ImageFormat::Png).then(|| {
+    widths
+      .iter()
+      .chain([&width])
+      .map(|&target| variant(target, ImageFormat::WebP))
+      .collect::<Vec<_>>()
+  });
+
+  let join = |set: Vec<(u32, String)>| {
+    set
+      .into_iter()
+      .map(|(width, url)| format!("{} {}w", url, width))
+      .collect::<Vec<_>>()
+      .join(", ")
+  };
+  Ok(ResponsiveImage {
+    src: url.to_string(),
+    width: Some(width),
+    height: Some(height),
+    srcset: Some(join(srcset)),
+    webp_srcset: webp_srcset.map(join),
+    bytes: Some(bytes.len() as u64),
+  })
+}
+
+/// Decode, resize and encode a variant into `file`
+#[cfg(feature = "ssr")]
+fn render_variant(variant: &Variant, file: &std::path::Path) -> Result<(), String> {
+  use image::{DynamicImage, ImageFormat, ImageReader};
+
+  let bytes = std::fs::read(&variant.source).map_err(|e| e.to_string())?;
+  if format!("{:016x}", stable_hash(&[&bytes])) != variant.hash {
+    return Err("the file changed since the page was rendered".to_string());
+  }
+  let source = ImageReader::new(std::io::Cursor::new(&bytes))
+    .with_guessed_format()
+    .map_err(|e| e.to_string())?
+    .decode()
+    .map_err(|e| e.to_string())?;
+
+  let resized = if variant.width < source.width() {
+    source.resize(variant.width, u32::MAX, image::imageops::FilterType::CatmullRom)
+  } else {
+    source
+  };
+  // WebP and JPEG encoders only take 8-bit pixels; JPEG has no alpha channel
+  let resized = match (variant.format, resized.color().has_alpha()) {
+    (ImageFormat::Jpeg, _) | (ImageFormat::WebP, false) => {
+      DynamicImage::ImageRgb8(resized.to_rgb8())
+    }
+    (ImageFormat::WebP, true) => DynamicImage::ImageRgba8(resized.to_rgba8()),
+    _ => resized,
+  };
+
+  if let Some(dir) = file.parent() {
+    std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+  }
+  let partial = file.with_extension(format!("{}.partial", variant.format.extensions_str()[0]));
+  resized
+    .save_with_format(&partial, variant.format)
+    .map_err(|e| e.to_string())?;
+  std::fs::rename(&partial, file).map_err(|e| e.to_string())
+}
+
+/// 64-bit FNV-1a over `parts`, which unlike `DefaultHasher` gives the same value on every
+/// Rust release, so cached file names stay valid across upgrades
+#[cfg(feature = "ssr")]
+pub(crate) fn stable_hash(parts: &[&[u8]]) -> u64 {
+  let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+  for part in parts {
+    // 0xff never occurs in UTF-8, so `["ab", "c"]` and `["a", "bc"]` differ
+    for &byte in part.iter().chain(&[0xff]) {
+      hash ^= u64::from(byte);
+      hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+  }
+  hash
+}
+
+/// Path of a cached variant served under [`CACHE_URL`], rendering it on first request.
+///
+/// Decoding and resizing can take a while, so call this from a blocking task.
+#[cfg(feature = "ssr")]
+pub fn find_cached(file: &str) -> Option<std::path::PathBuf> {
+  use std::sync::Mutex;
+
+  // One image at a time, so a page full of new images doesn't take every core and two
+  // requests for the same variant don't both render it
+  static RENDERING: Mutex<()> = Mutex::new(());
+
+  let valid = !file.starts_with('.')
+    && file
+      .chars()
+      .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '.'));
+  if !valid {
+    return None;
+  }
+  let path = crate::config::site_config().image_cache_dir.join(file);
+  if path.is_file() {
+    return Some(path);
+  }
+
+  let variant = VARIANTS.lock().ok()?.get(file).cloned()?;
+  let _rendering = RENDERING.lock().ok()?;
+  if !path.is_file() {
+    if let Err(e) = render_variant(&variant, &path) {
+      eprintln!("Error processing image {}: {}", variant.source.display(), e);
+      return None;
+    }
+  }
+  Some(path)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[cfg(feature = "ssr")]
+  #[test]
+  fn stable_hash_is_fixed() {
+    assert_eq!(stable_hash(&[]), 0xcbf2_9ce4_8422_2325);
+    assert_ne!(stable_hash(&[b"ab", b"c"]), stable_hash(&[b"a", b"bc"]));
+    // Pinned: changing it renames every cached image and card
+    assert_eq!(stable_hash(&[b"foobar"]), 0x3452_4ba7_168a_2c15);
+  }
+}
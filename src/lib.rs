@@ -1,9 +1,29 @@
 pub mod app;
+pub mod authors;
 pub mod components;
+#[cfg(feature = "ssr")]
+pub mod config;
+pub mod dates;
+pub mod escape;
+pub mod front_matter;
+#[cfg(feature = "ssr")]
+pub mod highlight;
+pub mod images;
+#[cfg(feature = "ssr")]
+pub mod links;
+#[cfg(feature = "ssr")]
+pub mod math;
+#[cfg(feature = "ssr")]
+pub mod og_image;
 pub mod performance;
+pub mod pages;
 pub mod posts;
 pub mod rss;
+#[cfg(feature = "ssr")]
+pub mod sanitize;
+pub mod shortcodes;
 pub mod sitemap;
+pub mod wiki_links;
 
 #[cfg(feature = "hydrate")]
 #[wasm_bindgen::prelude::wasm_bindgen]
@@ -0,0 +1,264 @@
+// Internal link checking: links between posts, tag pages, heading anchors and local files
+
+use crate::images::{self, CACHE_URL};
+use crate::pages::load_pages;
+use crate::posts::{
+  find_post_asset, find_redirect, load_listed_posts, load_posts, LinkReference, PostError,
+};
+use std::collections::{HashMap, HashSet};
+
+/// What links can point at: the element ids of each visible post by slug, and the tags
+/// that have a page
+struct Targets {
+  posts: HashMap<String, HashSet<String>>,
+  tags: HashSet<String>,
+}
+
+/// Every link, anchor and local image in visible posts and in pages that points nowhere,
+/// sorted by file and line.
+///
+/// Checked: `/posts/<slug>` (following redirects) and files in its bundle, `[[wiki links]]`,
+/// `/tags/<tag>`, `#anchor`s in the same or the linked post, and images, `src` and `poster`
+/// files under `public/`. Links to the site's other routes and to other sites aren't checked.
+pub fn check_links() -> Vec<PostError> {
+  let posts = load_posts();
+  let pages = load_pages();
+  let targets = Targets {
+    posts: posts
+      .iter()
+      .map(|post| (post.slug.clone(), element_ids(&post.content)))
+      .collect(),
+    tags: load_listed_posts()
+      .into_iter()
+      .flat_map(|post| post.metadata.tags)
+      .collect(),
+  };
+
+  let documents = posts
+    .iter()
+    .map(|post| (post.source(), post.links(), &post.content))
+    .chain(
+      pages
+        .iter()
+        .map(|page| (page.source(), page.links(), &page.content)),
+    );
+  let mut broken = Vec::new();
+  for (path, links, content) in documents {
+    let own_ids = element_ids(content);
+    for link in links {
+      if let Some(message) = check_link(link, &own_ids, &targets) {
+        broken.push((path.to_path_buf(), link.line, link.url.clone(), message));
+      }
+    }
+  }
+
+  // Shortcodes can put the same URL on a line twice
+  broken.sort();
+  broken.dedup();
+  broken
+    .into_iter()
+    .map(|(path, line, url, message)| PostError::BrokenLink {
+      path,
+      line,
+      url,
+      message,
+    })
+    .collect()
+}
+
+/// Why `link` is broken, or `None` if it resolves or isn't an internal link
+fn check_link(
+  link: &LinkReference,
+  own_ids: &HashSet<String>,
+  targets: &Targets,
+) -> Option<String> {
+  let url = link.url.trim();
+  // Wiki links are only recorded as written when they name no post
+  if let Some(target) = url.strip_prefix("[[").and_then(|url| url.strip_suffix("]]")) {
+    let name = target.split('#').next().unwrap_or_default().trim();
+    return Some(format!("no post named `{}`", name));
+  }
+  let (url, fragment) = match url.split_once('#') {
+    Some((url, fragment)) => (url, Some(percent_decode(fragment))),
+    None => (url, None),
+  };
+  let path = url.split('?').next().unwrap_or_default();
+
+  if path.is_empty() {
+    return match fragment {
+      Some(fragment) if !fragment.is_empty() && !own_ids.contains(&fragment) => Some(format!(
+        "no heading or element with id `{}` on this page",
+        fragment
+      )),
+      _ => None,
+    };
+  }
+
+  if let Some(rest) = path.strip_prefix("/posts/") {
+    let rest = rest.trim_end_matches('/');
+    if let Some((slug, file)) = rest.split_once('/') {
+      let file = percent_decode(file);
+      return find_post_asset(slug, &file)
+        .is_none()
+        .then(|| format!("no file `{}` in the bundle of post `{}`", file, slug));
+    }
+
+    let slug = match targets.posts.contains_key(rest) {
+      true => rest.to_string(),
+      false => match find_redirect(path) {
+        Some(target) => target.trim_start_matches("/posts/").to_string(),
+        None => return Some(format!("no post with slug `{}`", rest)),
+      },
+    };
+    return match (fragment, targets.posts.get(&slug)) {
+      (Some(fragment), Some(ids)) if !fragment.is_empty() && !ids.contains(&fragment) => {
+        Some(format!(
+          "no heading or element with id `{}` in post `{}`",
+          fragment, slug
+        ))
+      }
+      _ => None,
+    };
+  }
+
+  if let Some(tag) = path.strip_prefix("/tags/") {
+    let tag = percent_decode(tag.trim_end_matches('/'));
+    return (!targets.tags.contains(&tag)).then(|| format!("no post is tagged `{}`", tag));
+  }
+
+  let is_local = path.starts_with('/') && !path.starts_with("//");
+  if link.embedded && is_local && !path.starts_with(CACHE_URL) && !path.starts_with("/og/") {
+    let file = percent_decode(path);
+    return images::local_path(&file, None)
+      .is_none()
+      .then(|| format!("no file `{}` in public/", file.trim_start_matches('/')));
+  }
+
+  None
+}
+
+/// Values of the `id` attributes in rendered HTML
+fn element_ids(html: &str) -> HashSet<String> {
+  html
+    .match_indices(" id=\"")
+    .filter_map(|(index, attribute)| {
+      let start = index + attribute.len();
+      let end = html[start..].find('"')?;
+      Some(unescape(&html[start..start + end]))
+    })
+    .collect()
+}
+
+fn unescape(value: &str) -> String {
+  value
+    .replace("&quot;", "\"")
+    .replace("&lt;", "<")
+    .replace("&gt;", ">")
+    .replace("&amp;", "&")
+}
+
+/// Decode `%XX` escapes, so `/tags/r%C3%A9sum%C3%A9` matches the tag `résumé`
+fn percent_decode(text: &str) -> String {
+  let bytes = text.as_bytes();
+  let mut decoded = Vec::with_capacity(bytes.len());
+  let mut index = 0;
+  while index < bytes.len() {
+    // `from_str_radix` would also take a sign, as in `%+1`
+    let hex = bytes
+      .get(index + 1..index + 3)
+      .filter(|hex| hex.iter().all(u8::is_ascii_hexdigit))
+      .and_then(|hex| std::str::from_utf8(hex).ok())
+      .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+    match (bytes[index], hex) {
+      (b'%', Some(byte)) => {
+        decoded.push(byte);
+        index += 3;
+      }
+      (byte, _) => {
+        decoded.push(byte);
+        index += 1;
+      }
+    }
+  }
+  String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn check(url: &str) -> Option<String> {
+    let link = LinkReference {
+      url: url.to_string(),
+      line: 1,
+      embedded: false,
+    };
+    let ids = |ids: &[&str]| ids.iter().map(|id| id.to_string()).collect::<HashSet<_>>();
+    let targets = Targets {
+      posts: HashMap::from([("hello".to_string(), ids(&["intro", "한글-제목"]))]),
+      tags: HashSet::from(["rust".to_string(), "résumé".to_string()]),
+    };
+    check_link(&link, &ids(&["setup", "a&b"]), &targets)
+  }
+
+  #[test]
+  fn anchors_on_the_same_page() {
+    assert_eq!(check("#setup"), None);
+    assert_eq!(check("#a&b"), None);
+    assert_eq!(check("#"), None);
+    assert_eq!(
+      check("#missing"),
+      Some("no heading or element with id `missing` on this page".to_string())
+    );
+  }
+
+  #[test]
+  fn anchors_in_other_posts() {
+    assert_eq!(check("/posts/hello"), None);
+    assert_eq!(check("/posts/hello/#intro"), None);
+    assert_eq!(check("/posts/hello?ref=feed#intro"), None);
+    assert_eq!(check("/posts/hello#%ED%95%9C%EA%B8%80-%EC%A0%9C%EB%AA%A9"), None);
+    assert_eq!(
+      check("/posts/hello#outro"),
+      Some("no heading or element with id `outro` in post `hello`".to_string())
+    );
+  }
+
+  #[test]
+  fn tags_wiki_links_and_other_urls() {
+    assert_eq!(check("/tags/rust/"), None);
+    assert_eq!(check("/tags/r%C3%A9sum%C3%A9"), None);
+    assert_eq!(check("/tags/go"), Some("no post is tagged `go`".to_string()));
+    assert_eq!(
+      check("[[missing#Some Heading]]"),
+      Some("no post named `missing`".to_string())
+    );
+    for url in [
+      "https://example.com/posts/nope",
+      "//cdn.example.com/a.png",
+      "/about",
+      "mailto:a@b",
+    ] {
+      assert_eq!(check(url), None, "{}", url);
+    }
+  }
+
+  #[test]
+  fn element_ids_are_unescaped() {
+    let html = r#"<h2 id="a&amp;b">A</h2><p class="x" id="note">text</p>"#;
+    assert_eq!(
+      element_ids(html),
+      HashSet::from(["a&b".to_string(), "note".to_string()])
+    );
+  }
+
+  #[test]
+  fn percent_decoding() {
+    assert_eq!(percent_decode("r%C3%A9sum%C3%A9"), "résumé");
+    assert_eq!(percent_decode("a%20b%2fc"), "a b/c");
+    assert_eq!(percent_decode("100%"), "100%");
+    assert_eq!(percent_decode("%zz%4"), "%zz%4");
+    assert_eq!(percent_decode("%+1"), "%+1");
+    assert_eq!(percent_decode("%FF"), "\u{FFFD}");
+  }
+}
@@ -2,21 +2,56 @@
 #[tokio::main]
 async fn main() {
   use axum::{
-    http::{header, HeaderValue, StatusCode},
+    http::{header, HeaderValue, Method, StatusCode},
     middleware::{self, Next},
-    response::{IntoResponse, Response},
+    response::{Html, IntoResponse, Response},
     Router,
   };
   // IMPORTANT: If you changed the package name in Cargo.toml, update these imports
   // Replace blog_starter_rs with your_package_name (hyphens become underscores)
   use blog_starter_rs::app::*;
-  use blog_starter_rs::posts::load_posts;
+  use blog_starter_rs::escape::escape_html;
+  use blog_starter_rs::pages::{load_page_errors, load_page_warnings, load_pages};
+  use blog_starter_rs::posts::{
+    find_post_asset, find_redirect, load_listed_posts, load_post_errors, load_post_warnings,
+    PostError,
+  };
   use blog_starter_rs::rss::generate_rss;
   use blog_starter_rs::sitemap::{generate_robots_txt, generate_sitemap};
+  use leptos::config::Env;
   use leptos::logging::log;
   use leptos::prelude::*;
   use leptos_axum::{generate_route_list, LeptosRoutes};
 
+  // Register custom markdown shortcodes here, before posts are loaded, e.g.
+  // blog_starter_rs::shortcodes::register("kbd", |shortcode| { ... });
+
+  // Load code highlighting syntaxes and themes (see BLOG_HIGHLIGHT_DIR)
+  blog_starter_rs::highlight::init();
+
+  // `blog-starter-rs check`: report content that fails to load, has problems such as invalid
+  // math, or links nowhere, then exit
+  let mut warnings = load_post_warnings();
+  warnings.extend(load_page_warnings());
+  let broken_links = blog_starter_rs::links::check_links();
+  if std::env::args().nth(1).as_deref() == Some("check") {
+    let load_errors = load_post_errors().len() + load_page_errors().len();
+    for warning in warnings.iter().chain(&broken_links) {
+      println!("{}", warning);
+    }
+    println!(
+      "{} broken link(s), {} warning(s), {} error(s) loading posts and pages",
+      broken_links.len(),
+      warnings.len(),
+      load_errors
+    );
+    let clean = broken_links.is_empty() && warnings.is_empty() && load_errors == 0;
+    std::process::exit(if clean { 0 } else { 1 });
+  }
+  for warning in warnings.iter().chain(&broken_links) {
+    eprintln!("Warning: {}", warning);
+  }
+
   let conf = get_configuration(None).unwrap();
   let addr = conf.leptos_options.site_addr;
   let leptos_options = conf.leptos_options;
@@ -28,7 +63,10 @@ async fn main() {
     let path = req.uri().path().to_string();
     let mut response = next.run(req).await;
 
-    // Apply cache headers to static assets
+    // Apply cache headers to static assets, unless the handler chose its own
+    if response.headers().contains_key(header::CACHE_CONTROL) {
+      return response;
+    }
     if path.starts_with("/pkg/")
       || path.ends_with(".css")
       || path.ends_with(".js")
@@ -48,9 +86,100 @@ async fn main() {
     response
   }
 
+  // Files at the top of the site root, such as favicon.ico, win over the `/:page` route for
+  // standalone pages
+  async fn site_file_middleware(
+    axum::extract::State(options): axum::extract::State<LeptosOptions>,
+    req: axum::extract::Request,
+    next: Next,
+  ) -> Response {
+    let name = req.uri().path().trim_start_matches('/').to_string();
+    let path = std::path::Path::new(&*options.site_root).join(&name);
+    if name.is_empty() || name.contains('/') || !path.is_file() {
+      return next.run(req).await;
+    }
+
+    match tower_http::services::ServeFile::new(path).try_call(req).await {
+      Ok(response) => response.into_response(),
+      Err(e) => {
+        eprintln!("Error serving {}: {}", name, e);
+        StatusCode::INTERNAL_SERVER_ERROR.into_response()
+      }
+    }
+  }
+
+  // Permanent redirects from old post URLs (filename slugs and `aliases`) to the current slug
+  async fn redirect_middleware(req: axum::extract::Request, next: Next) -> Response {
+    match find_redirect(req.uri().path()) {
+      Some(target) => {
+        let location = match req.uri().query() {
+          Some(query) => format!("{}?{}", target, query),
+          None => target,
+        };
+        (StatusCode::MOVED_PERMANENTLY, [(header::LOCATION, location)]).into_response()
+      }
+      None => next.run(req).await,
+    }
+  }
+
+  // Development error page: show posts and pages that failed to load instead of silently skipping them
+  async fn post_errors_middleware(req: axum::extract::Request, next: Next) -> Response {
+    let wants_html = req.method() == Method::GET
+      && req
+        .headers()
+        .get(header::ACCEPT)
+        .and_then(|accept| accept.to_str().ok())
+        .is_some_and(|accept| accept.contains("text/html"));
+    if !wants_html {
+      return next.run(req).await;
+    }
+
+    let mut errors = load_post_errors();
+    errors.extend(load_page_errors());
+    if errors.is_empty() {
+      return next.run(req).await;
+    }
+
+    (
+      StatusCode::INTERNAL_SERVER_ERROR,
+      Html(render_post_errors(&errors)),
+    )
+      .into_response()
+  }
+
+  fn render_post_errors(errors: &[PostError]) -> String {
+    let items: String = errors
+      .iter()
+      .map(|error| format!("<li><pre>{}</pre></li>", escape_html(&error.to_string())))
+      .collect();
+
+    format!(
+      r#"<!DOCTYPE html>
+<html lang="en">
+  <head>
+    <meta charset="utf-8"/>
+    <title>Failed to load content</title>
+    <style>
+      body {{ font-family: sans-serif; background: #1e1e1e; color: #eee; padding: 2rem; }}
+      h1 {{ color: #ff6b6b; }}
+      pre {{ white-space: pre-wrap; background: #2d2d2d; padding: 1rem; border-radius: 8px; }}
+      ul {{ list-style: none; padding: 0; }}
+    </style>
+  </head>
+  <body>
+    <h1>{} file(s) failed to load</h1>
+    <p>Fix the errors below and restart the server. This page is only shown in development.</p>
+    <ul>{}</ul>
+  </body>
+</html>"#,
+      errors.len(),
+      items
+    )
+  }
+
   // RSS handler
   async fn rss_handler() -> Response {
-    let posts = load_posts();
+    let posts = load_listed_posts();
     let rss_content = generate_rss(&posts);
     (
       StatusCode::OK,
@@ -62,8 +191,8 @@ async fn main() {
 
   // Sitemap handler
   async fn sitemap_handler() -> Response {
-    let posts = load_posts();
-    let sitemap_content = generate_sitemap(&posts);
+    let posts = load_listed_posts();
+    let sitemap_content = generate_sitemap(&posts, &load_pages());
     (
       StatusCode::OK,
       [(header::CONTENT_TYPE, "application/xml; charset=utf-8")],
@@ -72,6 +201,90 @@ async fn main() {
       .into_response()
   }
 
+  // Files co-located with a post in a `posts/<slug>/` bundle
+  async fn post_asset_handler(
+    axum::extract::Path((slug, file)): axum::extract::Path<(String, String)>,
+    req: axum::extract::Request,
+  ) -> Response {
+    let Some(path) = find_post_asset(&slug, &file) else {
+      return StatusCode::NOT_FOUND.into_response();
+    };
+
+    match tower_http::services::ServeFile::new(path).try_call(req).await {
+      Ok(response) => response.into_response(),
+      Err(e) => {
+        eprintln!("Error serving {}/{}: {}", slug, file, e);
+        StatusCode::INTERNAL_SERVER_ERROR.into_response()
+      }
+    }
+  }
+
+  // Resized and WebP versions of post images; names contain a content hash so they never change
+  async fn image_handler(
+    axum::extract::Path(file): axum::extract::Path<String>,
+    req: axum::extract::Request,
+  ) -> Response {
+    // A variant is rendered on its first request, which decodes and resizes the image
+    let name = file.clone();
+    let found = tokio::task::spawn_blocking(move || blog_starter_rs::images::find_cached(&name));
+    let Ok(Some(path)) = found.await else {
+      return StatusCode::NOT_FOUND.into_response();
+    };
+
+    match tower_http::services::ServeFile::new(path).try_call(req).await {
+      Ok(response) => {
+        let mut response = response.into_response();
+        if response.status().is_success() {
+          response.headers_mut().insert(
+            header::CACHE_CONTROL,
+            HeaderValue::from_static("public, max-age=31536000, immutable"),
+          );
+        }
+        response
+      }
+      Err(e) => {
+        eprintln!("Error serving image {}: {}", file, e);
+        StatusCode::INTERNAL_SERVER_ERROR.into_response()
+      }
+    }
+  }
+
+  // Open Graph card for a post, `/og/<slug>.png`
+  async fn og_image_handler(
+    axum::extract::Path(file): axum::extract::Path<String>,
+  ) -> Response {
+    let Some(slug) = file.strip_suffix(".png").map(str::to_string) else {
+      return StatusCode::NOT_FOUND.into_response();
+    };
+    let card = tokio::task::spawn_blocking(move || blog_starter_rs::og_image::card_png(&slug));
+    let Ok(Some(png)) = card.await else {
+      return StatusCode::NOT_FOUND.into_response();
+    };
+
+    (
+      StatusCode::OK,
+      [
+        (header::CONTENT_TYPE, "image/png"),
+        (header::CACHE_CONTROL, "public, max-age=86400"),
+      ],
+      png,
+    )
+      .into_response()
+  }
+
+  // Syntax highlighting colors for code blocks, generated from the highlight themes
+  async fn highlight_css_handler() -> Response {
+    (
+      StatusCode::OK,
+      [
+        (header::CONTENT_TYPE, "text/css; charset=utf-8"),
+        (header::CACHE_CONTROL, "public, max-age=3600"),
+      ],
+      blog_starter_rs::highlight::stylesheet(),
+    )
+      .into_response()
+  }
+
   // Robots.txt handler
   async fn robots_handler() -> Response {
     let robots_content = generate_robots_txt();
@@ -83,17 +296,30 @@ async fn main() {
       .into_response()
   }
 
-  let app = Router::new()
+  let mut app = Router::new()
     .route("/rss.xml", axum::routing::get(rss_handler))
     .route("/sitemap.xml", axum::routing::get(sitemap_handler))
     .route("/robots.txt", axum::routing::get(robots_handler))
+    .route("/highlight.css", axum::routing::get(highlight_css_handler))
+    .route("/posts/{slug}/{*file}", axum::routing::get(post_asset_handler))
+    .route("/_images/{file}", axum::routing::get(image_handler))
+    .route("/og/{file}", axum::routing::get(og_image_handler))
     .leptos_routes(&leptos_options, routes, {
       let leptos_options = leptos_options.clone();
       move || shell(leptos_options.clone())
     })
     .fallback(leptos_axum::file_and_error_handler(shell))
+    .layer(middleware::from_fn_with_state(
+      leptos_options.clone(),
+      site_file_middleware,
+    ))
     .layer(middleware::from_fn(cache_middleware))
-    .with_state(leptos_options);
+    .layer(middleware::from_fn(redirect_middleware))
+    .with_state(leptos_options.clone());
+
+  if leptos_options.env == Env::DEV {
+    app = app.layer(middleware::from_fn(post_errors_middleware));
+  }
 
   // run our app with hyper
   // `axum::Server` is a re-export of `hyper::Server`
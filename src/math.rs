@@ -0,0 +1,1196 @@
+//! TeX math to MathML, rendered on the server for `$...$` and `$$...$$` in markdown.
+//!
+//! Covers the subset of LaTeX that shows up in blog posts: scripts, fractions, roots,
+//! Greek letters and common symbols, `\left`/`\right`, accents, font commands and the
+//! matrix, cases and aligned environments. Anything else is an error, so the caller can
+//! fall back to showing the source instead of rendering something wrong.
+
+use crate::escape::escape_html;
+
+/// Render TeX as a `<math>` element, keeping the source as an annotation for copy/paste
+pub fn to_mathml(tex: &str, display: bool) -> Result<String, String> {
+  let body = parse(tex)?;
+  Ok(format!(
+    r#"<math xmlns="http://www.w3.org/1998/Math/MathML" display="{}"><semantics>{}<annotation encoding="application/x-tex">{}</annotation></semantics></math>"#,
+    if display { "block" } else { "inline" },
+    body,
+    escape_html(tex.trim())
+  ))
+}
+
+/// Parse a complete formula into a single `<mrow>`
+fn parse(tex: &str) -> Result<String, String> {
+  let mut parser = Parser {
+    chars: tex.chars().collect(),
+    pos: 0,
+  };
+
+  let row = parser.parse_row()?;
+  match parser.peek() {
+    None => Ok(mrow(row)),
+    Some('}') => Err("unexpected `}` without a matching `{`".to_string()),
+    Some('&') => Err("`&` is only allowed inside an environment such as `aligned`".to_string()),
+    _ if parser.at_str("\\\\") => {
+      Err("`\\\\` is only allowed inside an environment such as `aligned`".to_string())
+    }
+    _ if parser.at_command("end") => Err("`\\end` without a matching `\\begin`".to_string()),
+    _ if parser.at_command("right") => {
+      Err("`\\right` without a matching `\\left`".to_string())
+    }
+    Some(c) => Err(format!("unexpected `{}`", c)),
+  }
+}
+
+/// A rendered piece of MathML
+struct Node {
+  ml: String,
+  /// Scripts go above and below (`\sum`, `\lim`) instead of to the side
+  limits: bool,
+}
+
+impl Node {
+  fn new(ml: impl Into<String>) -> Self {
+    Node {
+      ml: ml.into(),
+      limits: false,
+    }
+  }
+}
+
+fn mrow(nodes: Vec<Node>) -> String {
+  let inner: String = nodes.into_iter().map(|node| node.ml).collect();
+  format!("<mrow>{}</mrow>", inner)
+}
+
+/// A `{...}` group: a single node stays as it is so `\sum` keeps its limits
+fn group(mut nodes: Vec<Node>) -> Node {
+  if nodes.len() == 1 {
+    nodes.remove(0)
+  } else {
+    Node::new(mrow(nodes))
+  }
+}
+
+fn mo(text: &str) -> Node {
+  Node::new(format!("<mo>{}</mo>", escape_html(text)))
+}
+
+fn fence(delimiter: &str) -> String {
+  if delimiter.is_empty() {
+    String::new()
+  } else {
+    format!(
+      r#"<mo fence="true" stretchy="true" symmetric="true">{}</mo>"#,
+      escape_html(delimiter)
+    )
+  }
+}
+
+fn space(width: &str) -> Node {
+  Node::new(format!(r#"<mspace width="{}"></mspace>"#, width))
+}
+
+struct Parser {
+  chars: Vec<char>,
+  pos: usize,
+}
+
+impl Parser {
+  fn peek(&self) -> Option<char> {
+    self.chars.get(self.pos).copied()
+  }
+
+  fn peek_at(&self, offset: usize) -> Option<char> {
+    self.chars.get(self.pos + offset).copied()
+  }
+
+  fn eat(&mut self, c: char) -> bool {
+    if self.peek() == Some(c) {
+      self.pos += 1;
+      true
+    } else {
+      false
+    }
+  }
+
+  fn at_str(&self, s: &str) -> bool {
+    s.chars()
+      .enumerate()
+      .all(|(offset, c)| self.peek_at(offset) == Some(c))
+  }
+
+  fn eat_str(&mut self, s: &str) -> bool {
+    if self.at_str(s) {
+      self.pos += s.chars().count();
+      true
+    } else {
+      false
+    }
+  }
+
+  /// Whether the input continues with `\name` as a whole command
+  fn at_command(&self, name: &str) -> bool {
+    let len = name.chars().count();
+    self.peek() == Some('\\')
+      && name
+        .chars()
+        .enumerate()
+        .all(|(offset, c)| self.peek_at(offset + 1) == Some(c))
+      && !self
+        .peek_at(len + 1)
+        .is_some_and(|c| c.is_ascii_alphabetic())
+  }
+
+  fn eat_command(&mut self, name: &str) -> bool {
+    if self.at_command(name) {
+      self.pos += name.chars().count() + 1;
+      true
+    } else {
+      false
+    }
+  }
+
+  /// Skip whitespace and `%` comments, which TeX ignores in math
+  fn skip_whitespace(&mut self) {
+    while let Some(c) = self.peek() {
+      if c.is_whitespace() {
+        self.pos += 1;
+      } else if c == '%' {
+        while self.peek().is_some_and(|c| c != '\n') {
+          self.pos += 1;
+        }
+      } else {
+        break;
+      }
+    }
+  }
+
+  fn at_row_end(&self) -> bool {
+    matches!(self.peek(), None | Some('}') | Some('&'))
+      || self.at_str("\\\\")
+      || self.at_command("end")
+      || self.at_command("right")
+  }
+
+  /// Parse up to the end of the group or cell; `a \over b` turns it into a fraction
+  fn parse_row(&mut self) -> Result<Vec<Node>, String> {
+    let mut row = Vec::new();
+    let mut numerator: Option<Vec<Node>> = None;
+    loop {
+      self.skip_whitespace();
+      if self.at_row_end() {
+        return Ok(match numerator {
+          Some(numerator) => vec![Node::new(format!(
+            "<mfrac>{}{}</mfrac>",
+            mrow(numerator),
+            mrow(row)
+          ))],
+          None => row,
+        });
+      }
+      if self.eat_command("over") {
+        if numerator.is_some() {
+          return Err("more than one `\\over` in a group (use `\\frac`)".to_string());
+        }
+        numerator = Some(std::mem::take(&mut row));
+        continue;
+      }
+      let base = if matches!(self.peek(), Some('^') | Some('_')) {
+        Some(Node::new("<mrow></mrow>"))
+      } else {
+        self.parse_atom()?
+      };
+      if let Some(base) = base {
+        row.push(self.parse_scripts(base)?);
+      }
+    }
+  }
+
+  /// Attach any `^`, `_` and `'` that follow `base`
+  fn parse_scripts(&mut self, mut base: Node) -> Result<Node, String> {
+    let mut sub: Option<String> = None;
+    let mut sup: Option<String> = None;
+    let mut primes = String::new();
+
+    loop {
+      self.skip_whitespace();
+      if self.eat_command("limits") {
+        base.limits = true;
+        continue;
+      }
+      if self.eat_command("nolimits") {
+        base.limits = false;
+        continue;
+      }
+      match self.peek() {
+        Some('\'') => {
+          self.pos += 1;
+          primes.push('′');
+        }
+        Some('^') => {
+          self.pos += 1;
+          if sup.is_some() {
+            return Err("double superscript (use braces, e.g. `x^{a^b}`)".to_string());
+          }
+          sup = Some(self.parse_arg("`^`")?.ml);
+        }
+        Some('_') => {
+          self.pos += 1;
+          if sub.is_some() {
+            return Err("double subscript (use braces, e.g. `x_{a_b}`)".to_string());
+          }
+          sub = Some(self.parse_arg("`_`")?.ml);
+        }
+        _ => break,
+      }
+    }
+
+    if !primes.is_empty() {
+      let primes = format!("<mo>{}</mo>", primes);
+      sup = Some(match sup {
+        Some(sup) => format!("<mrow>{}{}</mrow>", primes, sup),
+        None => primes,
+      });
+    }
+
+    let (under, over, both) = if base.limits {
+      ("munder", "mover", "munderover")
+    } else {
+      ("msub", "msup", "msubsup")
+    };
+    let ml = match (sub, sup) {
+      (None, None) => return Ok(base),
+      (Some(sub), None) => format!("<{0}>{1}{2}</{0}>", under, base.ml, sub),
+      (None, Some(sup)) => format!("<{0}>{1}{2}</{0}>", over, base.ml, sup),
+      (Some(sub), Some(sup)) => format!("<{0}>{1}{2}{3}</{0}>", both, base.ml, sub, sup),
+    };
+    Ok(Node::new(ml))
+  }
+
+  /// A command or script argument: a `{...}` group or a single token (`\frac12` is ½)
+  fn parse_arg(&mut self, what: &str) -> Result<Node, String> {
+    self.skip_whitespace();
+    if self.at_row_end() || matches!(self.peek(), Some('^') | Some('_')) {
+      return Err(format!("missing argument for {}", what));
+    }
+    if let Some(digit) = self.peek().filter(char::is_ascii_digit) {
+      self.pos += 1;
+      return Ok(Node::new(format!("<mn>{}</mn>", digit)));
+    }
+    self
+      .parse_atom()?
+      .ok_or_else(|| format!("missing argument for {}", what))
+  }
+
+  /// The raw source of a `{...}` argument, or of a single character or command
+  fn read_raw_arg(&mut self, what: &str) -> Result<String, String> {
+    self.skip_whitespace();
+    match self.peek() {
+      Some('{') => {
+        self.pos += 1;
+        let mut depth = 0;
+        let mut raw = String::new();
+        loop {
+          match self.peek() {
+            None => return Err(format!("missing `}}` after the argument of {}", what)),
+            Some('\\') => {
+              raw.push('\\');
+              self.pos += 1;
+              if let Some(c) = self.peek() {
+                raw.push(c);
+                self.pos += 1;
+              }
+            }
+            Some('}') if depth == 0 => {
+              self.pos += 1;
+              return Ok(raw);
+            }
+            Some(c) => {
+              match c {
+                '{' => depth += 1,
+                '}' => depth -= 1,
+                _ => {}
+              }
+              raw.push(c);
+              self.pos += 1;
+            }
+          }
+        }
+      }
+      Some('\\') => {
+        self.pos += 1;
+        Ok(format!("\\{}", self.read_command_name()?))
+      }
+      Some(c) if !self.at_row_end() => {
+        self.pos += 1;
+        Ok(c.to_string())
+      }
+      _ => Err(format!("missing argument for {}", what)),
+    }
+  }
+
+  fn read_command_name(&mut self) -> Result<String, String> {
+    match self.peek() {
+      None => Err("`\\` at the end of the formula".to_string()),
+      Some(c) if c.is_ascii_alphabetic() => {
+        let mut name = String::new();
+        while let Some(c) = self.peek().filter(char::is_ascii_alphabetic) {
+          name.push(c);
+          self.pos += 1;
+        }
+        Ok(name)
+      }
+      Some(c) => {
+        self.pos += 1;
+        Ok(c.to_string())
+      }
+    }
+  }
+
+  /// A delimiter after `\left`, `\right`, `\big` and friends; `.` is an empty one
+  fn read_delimiter(&mut self, after: &str) -> Result<String, String> {
+    self.skip_whitespace();
+    let delimiter = match self.peek() {
+      Some('.') => String::new(),
+      Some(c @ ('(' | ')' | '[' | ']' | '|' | '/')) => c.to_string(),
+      Some('<') => "⟨".to_string(),
+      Some('>') => "⟩".to_string(),
+      Some('\\') => {
+        self.pos += 1;
+        let name = self.read_command_name()?;
+        return symbol(DELIMITERS, &name)
+          .map(str::to_string)
+          .ok_or_else(|| format!("`\\{}` is not a delimiter (after `\\{}`)", name, after));
+      }
+      _ => return Err(format!("missing delimiter after `\\{}`", after)),
+    };
+    self.pos += 1;
+    Ok(delimiter)
+  }
+
+  /// One token; `None` for commands that only change spacing or style hints we ignore
+  fn parse_atom(&mut self) -> Result<Option<Node>, String> {
+    let Some(c) = self.peek() else {
+      return Ok(None);
+    };
+    self.pos += 1;
+
+    let node = match c {
+      '{' => {
+        let row = self.parse_row()?;
+        if !self.eat('}') {
+          return Err("missing `}`".to_string());
+        }
+        group(row)
+      }
+      '\\' => return self.parse_command(),
+      '0'..='9' | '.' if c != '.' || self.peek().is_some_and(|c| c.is_ascii_digit()) => {
+        let mut number = c.to_string();
+        while let Some(c) = self.peek() {
+          let decimal_point = c == '.'
+            && !number.contains('.')
+            && self.peek_at(1).is_some_and(|c| c.is_ascii_digit());
+          if !c.is_ascii_digit() && !decimal_point {
+            break;
+          }
+          number.push(c);
+          self.pos += 1;
+        }
+        Node::new(format!("<mn>{}</mn>", number))
+      }
+      '(' | ')' | '[' | ']' | '|' => Node::new(format!(r#"<mo stretchy="false">{}</mo>"#, c)),
+      '\'' => mo("′"),
+      '-' => mo("−"),
+      '*' => mo("∗"),
+      '~' => space("0.333em"),
+      '$' | '#' => return Err(format!("unexpected `{}`", c)),
+      c if c.is_alphabetic() => Node::new(format!("<mi>{}</mi>", escape_html(&c.to_string()))),
+      c => mo(&c.to_string()),
+    };
+    Ok(Some(node))
+  }
+
+  fn parse_command(&mut self) -> Result<Option<Node>, String> {
+    let name = self.read_command_name()?;
+
+    let node = match name.as_str() {
+      "," | "thinspace" => space("0.1667em"),
+      ":" | ">" | "medspace" => space("0.2222em"),
+      ";" | "thickspace" => space("0.2778em"),
+      "!" | "negthinspace" => space("-0.1667em"),
+      " " => space("0.333em"),
+      "enspace" => space("0.5em"),
+      "quad" => space("1em"),
+      "qquad" => space("2em"),
+      "%" | "$" | "#" | "&" | "_" => mo(&name),
+      "displaystyle" | "textstyle" | "scriptstyle" | "limits" | "nolimits" | "nonumber"
+      | "notag" => return Ok(None),
+      "frac" | "dfrac" | "tfrac" | "cfrac" => {
+        let numerator = self.parse_arg(&format!("`\\{}`", name))?;
+        let denominator = self.parse_arg(&format!("`\\{}`", name))?;
+        let fraction = format!("<mfrac>{}{}</mfrac>", numerator.ml, denominator.ml);
+        Node::new(match name.as_str() {
+          "dfrac" | "cfrac" => format!(
+            r#"<mstyle displaystyle="true" scriptlevel="0">{}</mstyle>"#,
+            fraction
+          ),
+          "tfrac" => format!(r#"<mstyle displaystyle="false">{}</mstyle>"#, fraction),
+          _ => fraction,
+        })
+      }
+      "binom" => {
+        let top = self.parse_arg("`\\binom`")?;
+        let bottom = self.parse_arg("`\\binom`")?;
+        Node::new(format!(
+          r#"<mrow><mo>(</mo><mfrac linethickness="0">{}{}</mfrac><mo>)</mo></mrow>"#,
+          top.ml, bottom.ml
+        ))
+      }
+      "sqrt" => {
+        self.skip_whitespace();
+        let index = if self.eat('[') {
+          let start = self.pos;
+          let mut depth = 0;
+          loop {
+            match self.peek() {
+              None => return Err("missing `]` after the index of `\\sqrt`".to_string()),
+              Some(']') if depth == 0 => break,
+              Some('{') => depth += 1,
+              Some('}') => depth -= 1,
+              _ => {}
+            }
+            self.pos += 1;
+          }
+          let raw: String = self.chars[start..self.pos].iter().collect();
+          self.pos += 1;
+          Some(parse(&raw)?)
+        } else {
+          None
+        };
+        let radicand = self.parse_arg("`\\sqrt`")?;
+        Node::new(match index {
+          Some(index) => format!("<mroot>{}{}</mroot>", radicand.ml, index),
+          None => format!("<msqrt>{}</msqrt>", radicand.ml),
+        })
+      }
+      "text" | "textrm" | "textnormal" | "mbox" | "hbox" | "textit" | "textbf" => {
+        let text = self.read_raw_arg(&format!("`\\{}`", name))?;
+        let style = match name.as_str() {
+          "textit" => r#" style="font-style: italic""#,
+          "textbf" => r#" style="font-weight: bold""#,
+          _ => "",
+        };
+        // Token elements trim their whitespace, so keep spaces as no-break spaces
+        Node::new(format!(
+          "<mtext{}>{}</mtext>",
+          style,
+          escape_html(&text).replace(' ', "\u{a0}")
+        ))
+      }
+      "operatorname" => {
+        let limits = self.eat('*');
+        let text = self.read_raw_arg("`\\operatorname`")?;
+        let text = escape_html(text.trim());
+        if limits {
+          Node {
+            ml: format!(r#"<mo movablelimits="true" form="prefix">{}</mo>"#, text),
+            limits: true,
+          }
+        } else {
+          Node::new(format!(r#"<mi mathvariant="normal">{}</mi>"#, text))
+        }
+      }
+      "mathrm" | "mathup" => {
+        let raw = self.read_raw_arg(&format!("`\\{}`", name))?;
+        if raw.chars().all(|c| c.is_ascii_alphanumeric() || c == ' ') {
+          Node::new(format!(
+            r#"<mi mathvariant="normal">{}</mi>"#,
+            raw.replace(' ', "")
+          ))
+        } else {
+          Node::new(parse(&raw)?)
+        }
+      }
+      "mathbf" | "mathit" | "mathbb" | "mathcal" | "mathscr" | "mathfrak" | "mathsf" | "mathtt"
+      | "boldsymbol" | "bm" => {
+        let raw = self.read_raw_arg(&format!("`\\{}`", name))?;
+        if !raw.chars().all(|c| c.is_ascii_alphanumeric() || c == ' ') {
+          // Commands inside (e.g. `\boldsymbol{\alpha}`) render without the style
+          return Ok(Some(Node::new(parse(&raw)?)));
+        }
+        let nodes = raw
+          .chars()
+          .filter(|c| *c != ' ')
+          .map(|c| {
+            let styled = styled_char(c, &name).unwrap_or(c);
+            if c.is_ascii_digit() {
+              Node::new(format!("<mn>{}</mn>", styled))
+            } else {
+              Node::new(format!("<mi>{}</mi>", styled))
+            }
+          })
+          .collect();
+        group(nodes)
+      }
+      "left" => {
+        let open = self.read_delimiter("left")?;
+        let row = self.parse_row()?;
+        if !self.eat_command("right") {
+          return Err("`\\left` without a matching `\\right`".to_string());
+        }
+        let close = self.read_delimiter("right")?;
+        Node::new(format!(
+          "<mrow>{}{}{}</mrow>",
+          fence(&open),
+          mrow(row),
+          fence(&close)
+        ))
+      }
+      "middle" => {
+        let delimiter = self.read_delimiter("middle")?;
+        Node::new(format!(
+          r#"<mo stretchy="true" symmetric="true">{}</mo>"#,
+          escape_html(&delimiter)
+        ))
+      }
+      "big" | "bigl" | "bigr" | "bigm" | "Big" | "Bigl" | "Bigr" | "Bigm" | "bigg" | "biggl"
+      | "biggr" | "biggm" | "Bigg" | "Biggl" | "Biggr" | "Biggm" => {
+        let size = match name.trim_end_matches(['l', 'r', 'm']) {
+          "big" => "1.2em",
+          "Big" => "1.623em",
+          "bigg" => "2.047em",
+          _ => "2.470em",
+        };
+        let delimiter = self.read_delimiter(&name)?;
+        Node::new(format!(
+          r#"<mo fence="false" stretchy="true" symmetric="true" minsize="{0}" maxsize="{0}">{1}</mo>"#,
+          size,
+          escape_html(&delimiter)
+        ))
+      }
+      "not" => {
+        self.skip_whitespace();
+        let relation = self.parse_atom()?.map(|node| node.ml).unwrap_or_default();
+        match relation.strip_suffix("</mo>") {
+          Some(relation) if relation.starts_with("<mo") => {
+            Node::new(format!("{}\u{338}</mo>", relation))
+          }
+          _ => return Err("`\\not` must be followed by a relation such as `=`".to_string()),
+        }
+      }
+      "overset" | "stackrel" | "underset" => {
+        let script = self.parse_arg(&format!("`\\{}`", name))?;
+        let base = self.parse_arg(&format!("`\\{}`", name))?;
+        let tag = if name == "underset" {
+          "munder"
+        } else {
+          "mover"
+        };
+        Node::new(format!("<{0}>{1}{2}</{0}>", tag, base.ml, script.ml))
+      }
+      "underline" => {
+        let base = self.parse_arg("`\\underline`")?;
+        Node::new(format!(
+          r#"<munder accentunder="true">{}<mo stretchy="true">_</mo></munder>"#,
+          base.ml
+        ))
+      }
+      "overbrace" | "underbrace" => {
+        let base = self.parse_arg(&format!("`\\{}`", name))?;
+        let ml = if name == "overbrace" {
+          format!(r#"<mover>{}<mo stretchy="true">⏞</mo></mover>"#, base.ml)
+        } else {
+          format!(r#"<munder>{}<mo stretchy="true">⏟</mo></munder>"#, base.ml)
+        };
+        Node { ml, limits: true }
+      }
+      "boxed" => {
+        let content = self.parse_arg("`\\boxed`")?;
+        Node::new(format!(
+          r#"<mrow style="border: 1px solid; padding: 0.2em">{}</mrow>"#,
+          content.ml
+        ))
+      }
+      "pmod" => {
+        let modulus = self.parse_arg("`\\pmod`")?;
+        Node::new(format!(
+          r#"<mrow><mspace width="1em"></mspace><mo stretchy="false">(</mo><mi>mod</mi><mspace width="0.333em"></mspace>{}<mo stretchy="false">)</mo></mrow>"#,
+          modulus.ml
+        ))
+      }
+      "bmod" | "mod" => Node::new(r#"<mo lspace="0.2222em" rspace="0.2222em">mod</mo>"#),
+      "begin" => self.parse_environment()?,
+      _ => {
+        if let Some(&(_, accent, stretchy)) = ACCENTS.iter().find(|(accent, ..)| *accent == name) {
+          let base = self.parse_arg(&format!("`\\{}`", name))?;
+          Node::new(format!(
+            r#"<mover accent="true">{}<mo stretchy="{}">{}</mo></mover>"#,
+            base.ml, stretchy, accent
+          ))
+        } else if let Some(letter) = symbol(IDENTIFIERS, &name) {
+          Node::new(format!("<mi>{}</mi>", letter))
+        } else if let Some(letter) = symbol(UPRIGHT_IDENTIFIERS, &name) {
+          Node::new(format!(r#"<mi mathvariant="normal">{}</mi>"#, letter))
+        } else if let Some(operator) = symbol(OPERATORS, &name) {
+          mo(operator)
+        } else if let Some(delimiter) = symbol(DELIMITERS, &name) {
+          Node::new(format!(
+            r#"<mo stretchy="false">{}</mo>"#,
+            escape_html(delimiter)
+          ))
+        } else if let Some(operator) = symbol(LARGE_OPERATORS, &name) {
+          Node {
+            ml: format!("<mo>{}</mo>", operator),
+            limits: true,
+          }
+        } else if let Some(operator) = symbol(INTEGRALS, &name) {
+          mo(operator)
+        } else if let Some(function) = symbol(LIMIT_FUNCTIONS, &name) {
+          Node {
+            ml: format!(
+              r#"<mo movablelimits="true" form="prefix">{}</mo>"#,
+              function
+            ),
+            limits: true,
+          }
+        } else if FUNCTIONS.contains(&name.as_str()) {
+          Node::new(format!("<mi>{}</mi>", name))
+        } else {
+          return Err(format!("unknown command `\\{}`", name));
+        }
+      }
+    };
+    Ok(Some(node))
+  }
+
+  /// `\begin{name} ... \end{name}` with `&` between cells and `\\` between rows
+  fn parse_environment(&mut self) -> Result<Node, String> {
+    let name = self.read_raw_arg("`\\begin`")?;
+    let (open, close, columnalign, displaystyle) = match name.as_str() {
+      "matrix" | "smallmatrix" => ("", "", None, false),
+      "pmatrix" => ("(", ")", None, false),
+      "bmatrix" => ("[", "]", None, false),
+      "Bmatrix" => ("{", "}", None, false),
+      "vmatrix" => ("|", "|", None, false),
+      "Vmatrix" => ("‖", "‖", None, false),
+      "cases" => ("{", "", Some("left left"), false),
+      "aligned" | "align" | "align*" | "split" => {
+        ("", "", Some("right left right left right left"), true)
+      }
+      "gathered" | "gather" | "gather*" | "equation" | "equation*" => ("", "", None, true),
+      "array" => {
+        // Column spec such as `{cc|l}`; cells are centered regardless
+        self.read_raw_arg("`\\begin{array}`")?;
+        ("", "", None, false)
+      }
+      _ => return Err(format!("unknown environment `{}`", name)),
+    };
+
+    let mut rows: Vec<Vec<String>> = Vec::new();
+    loop {
+      let mut cells = Vec::new();
+      loop {
+        cells.push(mrow(self.parse_row()?));
+        if !self.eat('&') {
+          break;
+        }
+      }
+      rows.push(cells);
+      if self.eat_str("\\\\") {
+        continue;
+      }
+      if self.eat_command("end") {
+        break;
+      }
+      return Err(match self.peek() {
+        None => format!("missing `\\end{{{}}}`", name),
+        Some('}') => "unexpected `}` without a matching `{`".to_string(),
+        _ => "`\\right` without a matching `\\left`".to_string(),
+      });
+    }
+    let end = self.read_raw_arg("`\\end`")?;
+    if end != name {
+      return Err(format!(
+        "`\\begin{{{}}}` is closed by `\\end{{{}}}`",
+        name, end
+      ));
+    }
+    // A trailing `\\` before `\end` doesn't start a new row
+    if rows.len() > 1
+      && rows
+        .last()
+        .is_some_and(|row| row.len() == 1 && row[0] == "<mrow></mrow>")
+    {
+      rows.pop();
+    }
+
+    let rows: String = rows
+      .into_iter()
+      .map(|cells| {
+        let cells: String = cells
+          .into_iter()
+          .map(|cell| format!("<mtd>{}</mtd>", cell))
+          .collect();
+        format!("<mtr>{}</mtr>", cells)
+      })
+      .collect();
+    let mut attributes = String::new();
+    if let Some(columnalign) = columnalign {
+      attributes.push_str(&format!(r#" columnalign="{}""#, columnalign));
+    }
+    if displaystyle {
+      attributes.push_str(r#" displaystyle="true""#);
+    }
+    let table = format!("<mtable{}>{}</mtable>", attributes, rows);
+
+    Ok(Node::new(if open.is_empty() && close.is_empty() {
+      table
+    } else {
+      format!("<mrow>{}{}{}</mrow>", fence(open), table, fence(close))
+    }))
+  }
+}
+
+fn symbol(table: &[(&str, &'static str)], name: &str) -> Option<&'static str> {
+  table
+    .iter()
+    .find(|(command, _)| *command == name)
+    .map(|(_, symbol)| *symbol)
+}
+
+/// Map an ASCII letter or digit into a Unicode math alphabet (`\mathbb{R}` is ℝ)
+fn styled_char(c: char, command: &str) -> Option<char> {
+  // (uppercase A, lowercase a, digit 0, letters that live outside the block)
+  let (upper, lower, digit, exceptions): (u32, u32, Option<u32>, &[(char, char)]) = match command {
+    "mathbf" => (0x1D400, 0x1D41A, Some(0x1D7CE), &[]),
+    "mathit" => (0x1D434, 0x1D44E, None, &[('h', 'ℎ')]),
+    "boldsymbol" | "bm" => (0x1D468, 0x1D482, Some(0x1D7CE), &[]),
+    "mathbb" => (
+      0x1D538,
+      0x1D552,
+      Some(0x1D7D8),
+      &[
+        ('C', 'ℂ'),
+        ('H', 'ℍ'),
+        ('N', 'ℕ'),
+        ('P', 'ℙ'),
+        ('Q', 'ℚ'),
+        ('R', 'ℝ'),
+        ('Z', 'ℤ'),
+      ],
+    ),
+    "mathcal" | "mathscr" => (
+      0x1D49C,
+      0x1D4B6,
+      None,
+      &[
+        ('B', 'ℬ'),
+        ('E', 'ℰ'),
+        ('F', 'ℱ'),
+        ('H', 'ℋ'),
+        ('I', 'ℐ'),
+        ('L', 'ℒ'),
+        ('M', 'ℳ'),
+        ('R', 'ℛ'),
+        ('e', 'ℯ'),
+        ('g', 'ℊ'),
+        ('o', 'ℴ'),
+      ],
+    ),
+    "mathfrak" => (
+      0x1D504,
+      0x1D51E,
+      None,
+      &[('C', 'ℭ'), ('H', 'ℌ'), ('I', 'ℑ'), ('R', 'ℜ'), ('Z', 'ℨ')],
+    ),
+    "mathsf" => (0x1D5A0, 0x1D5BA, Some(0x1D7E2), &[]),
+    "mathtt" => (0x1D670, 0x1D68A, Some(0x1D7F6), &[]),
+    _ => return None,
+  };
+
+  if let Some(&(_, styled)) = exceptions.iter().find(|(plain, _)| *plain == c) {
+    return Some(styled);
+  }
+  let code = match c {
+    'A'..='Z' => upper + (c as u32 - 'A' as u32),
+    'a'..='z' => lower + (c as u32 - 'a' as u32),
+    '0'..='9' => digit? + (c as u32 - '0' as u32),
+    _ => return None,
+  };
+  char::from_u32(code)
+}
+
+/// Accent commands: (name, accent character, stretches over the base)
+const ACCENTS: &[(&str, &str, bool)] = &[
+  ("hat", "^", false),
+  ("widehat", "^", true),
+  ("check", "ˇ", false),
+  ("tilde", "~", false),
+  ("widetilde", "~", true),
+  ("bar", "¯", false),
+  ("overline", "‾", true),
+  ("vec", "→", false),
+  ("overrightarrow", "→", true),
+  ("overleftarrow", "←", true),
+  ("dot", "˙", false),
+  ("ddot", "¨", false),
+  ("acute", "´", false),
+  ("grave", "`", false),
+  ("breve", "˘", false),
+  ("mathring", "˚", false),
+];
+
+/// Letters and symbols rendered as identifiers (italic when they are letters)
+const IDENTIFIERS: &[(&str, &str)] = &[
+  ("alpha", "α"),
+  ("beta", "β"),
+  ("gamma", "γ"),
+  ("delta", "δ"),
+  ("epsilon", "ϵ"),
+  ("varepsilon", "ε"),
+  ("zeta", "ζ"),
+  ("eta", "η"),
+  ("theta", "θ"),
+  ("vartheta", "ϑ"),
+  ("iota", "ι"),
+  ("kappa", "κ"),
+  ("lambda", "λ"),
+  ("mu", "μ"),
+  ("nu", "ν"),
+  ("xi", "ξ"),
+  ("omicron", "ο"),
+  ("pi", "π"),
+  ("varpi", "ϖ"),
+  ("rho", "ρ"),
+  ("varrho", "ϱ"),
+  ("sigma", "σ"),
+  ("varsigma", "ς"),
+  ("tau", "τ"),
+  ("upsilon", "υ"),
+  ("phi", "ϕ"),
+  ("varphi", "φ"),
+  ("chi", "χ"),
+  ("psi", "ψ"),
+  ("omega", "ω"),
+  ("infty", "∞"),
+  ("partial", "∂"),
+  ("nabla", "∇"),
+  ("hbar", "ℏ"),
+  ("ell", "ℓ"),
+  ("aleph", "ℵ"),
+  ("wp", "℘"),
+  ("Re", "ℜ"),
+  ("Im", "ℑ"),
+  ("emptyset", "∅"),
+  ("varnothing", "∅"),
+  ("imath", "ı"),
+  ("jmath", "ȷ"),
+];
+
+/// Capital Greek letters, upright as in TeX
+const UPRIGHT_IDENTIFIERS: &[(&str, &str)] = &[
+  ("Gamma", "Γ"),
+  ("Delta", "Δ"),
+  ("Theta", "Θ"),
+  ("Lambda", "Λ"),
+  ("Xi", "Ξ"),
+  ("Pi", "Π"),
+  ("Sigma", "Σ"),
+  ("Upsilon", "Υ"),
+  ("Phi", "Φ"),
+  ("Psi", "Ψ"),
+  ("Omega", "Ω"),
+];
+
+const OPERATORS: &[(&str, &str)] = &[
+  ("pm", "±"),
+  ("mp", "∓"),
+  ("times", "×"),
+  ("div", "÷"),
+  ("cdot", "⋅"),
+  ("cdotp", "⋅"),
+  ("ast", "∗"),
+  ("star", "⋆"),
+  ("circ", "∘"),
+  ("bullet", "∙"),
+  ("diamond", "⋄"),
+  ("oplus", "⊕"),
+  ("ominus", "⊖"),
+  ("otimes", "⊗"),
+  ("oslash", "⊘"),
+  ("odot", "⊙"),
+  ("cap", "∩"),
+  ("cup", "∪"),
+  ("sqcap", "⊓"),
+  ("sqcup", "⊔"),
+  ("uplus", "⊎"),
+  ("setminus", "∖"),
+  ("wedge", "∧"),
+  ("land", "∧"),
+  ("vee", "∨"),
+  ("lor", "∨"),
+  ("neg", "¬"),
+  ("lnot", "¬"),
+  ("lt", "<"),
+  ("gt", ">"),
+  ("leq", "≤"),
+  ("le", "≤"),
+  ("geq", "≥"),
+  ("ge", "≥"),
+  ("leqslant", "⩽"),
+  ("geqslant", "⩾"),
+  ("nleq", "≰"),
+  ("ngeq", "≱"),
+  ("neq", "≠"),
+  ("ne", "≠"),
+  ("approx", "≈"),
+  ("equiv", "≡"),
+  ("sim", "∼"),
+  ("simeq", "≃"),
+  ("cong", "≅"),
+  ("doteq", "≐"),
+  ("asymp", "≍"),
+  ("lesssim", "≲"),
+  ("gtrsim", "≳"),
+  ("triangleq", "≜"),
+  ("coloneqq", "≔"),
+  ("propto", "∝"),
+  ("ll", "≪"),
+  ("gg", "≫"),
+  ("prec", "≺"),
+  ("succ", "≻"),
+  ("preceq", "⪯"),
+  ("succeq", "⪰"),
+  ("in", "∈"),
+  ("notin", "∉"),
+  ("ni", "∋"),
+  ("subset", "⊂"),
+  ("supset", "⊃"),
+  ("subseteq", "⊆"),
+  ("supseteq", "⊇"),
+  ("subsetneq", "⊊"),
+  ("supsetneq", "⊋"),
+  ("nsubseteq", "⊈"),
+  ("sqsubseteq", "⊑"),
+  ("sqsupseteq", "⊒"),
+  ("mid", "∣"),
+  ("nmid", "∤"),
+  ("parallel", "∥"),
+  ("perp", "⊥"),
+  ("vdash", "⊢"),
+  ("dashv", "⊣"),
+  ("models", "⊨"),
+  ("top", "⊤"),
+  ("bot", "⊥"),
+  ("to", "→"),
+  ("rightarrow", "→"),
+  ("leftarrow", "←"),
+  ("gets", "←"),
+  ("leftrightarrow", "↔"),
+  ("Rightarrow", "⇒"),
+  ("Leftarrow", "⇐"),
+  ("Leftrightarrow", "⇔"),
+  ("longrightarrow", "⟶"),
+  ("longleftarrow", "⟵"),
+  ("longleftrightarrow", "⟷"),
+  ("Longrightarrow", "⟹"),
+  ("Longleftarrow", "⟸"),
+  ("Longleftrightarrow", "⟺"),
+  ("implies", "⟹"),
+  ("impliedby", "⟸"),
+  ("iff", "⟺"),
+  ("mapsto", "↦"),
+  ("longmapsto", "⟼"),
+  ("hookrightarrow", "↪"),
+  ("hookleftarrow", "↩"),
+  ("rightleftharpoons", "⇌"),
+  ("nearrow", "↗"),
+  ("searrow", "↘"),
+  ("swarrow", "↙"),
+  ("nwarrow", "↖"),
+  ("forall", "∀"),
+  ("exists", "∃"),
+  ("nexists", "∄"),
+  ("therefore", "∴"),
+  ("because", "∵"),
+  ("angle", "∠"),
+  ("triangle", "△"),
+  ("square", "□"),
+  ("dagger", "†"),
+  ("ddagger", "‡"),
+  ("prime", "′"),
+  ("colon", ":"),
+  ("ldots", "…"),
+  ("dots", "…"),
+  ("cdots", "⋯"),
+  ("vdots", "⋮"),
+  ("ddots", "⋱"),
+];
+
+/// Symbols usable after `\left`/`\right`; they don't stretch when used on their own
+const DELIMITERS: &[(&str, &str)] = &[
+  ("{", "{"),
+  ("}", "}"),
+  ("lbrace", "{"),
+  ("rbrace", "}"),
+  ("|", "‖"),
+  ("langle", "⟨"),
+  ("rangle", "⟩"),
+  ("vert", "|"),
+  ("lvert", "|"),
+  ("rvert", "|"),
+  ("Vert", "‖"),
+  ("lVert", "‖"),
+  ("rVert", "‖"),
+  ("lfloor", "⌊"),
+  ("rfloor", "⌋"),
+  ("lceil", "⌈"),
+  ("rceil", "⌉"),
+  ("backslash", "\\"),
+  ("uparrow", "↑"),
+  ("downarrow", "↓"),
+  ("updownarrow", "↕"),
+  ("Uparrow", "⇑"),
+  ("Downarrow", "⇓"),
+];
+
+/// Big operators whose scripts become limits in display math
+const LARGE_OPERATORS: &[(&str, &str)] = &[
+  ("sum", "∑"),
+  ("prod", "∏"),
+  ("coprod", "∐"),
+  ("bigcup", "⋃"),
+  ("bigcap", "⋂"),
+  ("bigsqcup", "⨆"),
+  ("bigvee", "⋁"),
+  ("bigwedge", "⋀"),
+  ("bigoplus", "⨁"),
+  ("bigotimes", "⨂"),
+  ("bigodot", "⨀"),
+  ("biguplus", "⨄"),
+];
+
+const INTEGRALS: &[(&str, &str)] = &[
+  ("int", "∫"),
+  ("iint", "∬"),
+  ("iiint", "∭"),
+  ("oint", "∮"),
+  ("oiint", "∯"),
+];
+
+/// Named functions whose subscripts become limits in display math
+const LIMIT_FUNCTIONS: &[(&str, &str)] = &[
+  ("lim", "lim"),
+  ("liminf", "lim inf"),
+  ("limsup", "lim sup"),
+  ("max", "max"),
+  ("min", "min"),
+  ("sup", "sup"),
+  ("inf", "inf"),
+  ("det", "det"),
+  ("gcd", "gcd"),
+  ("Pr", "Pr"),
+];
+
+const FUNCTIONS: &[&str] = &[
+  "sin", "cos", "tan", "cot", "sec", "csc", "sinh", "cosh", "tanh", "coth", "arcsin", "arccos",
+  "arctan", "arg", "deg", "dim", "exp", "hom", "ker", "lg", "ln", "log",
+];
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn render(tex: &str) -> String {
+    parse(tex).unwrap_or_else(|error| panic!("{}: {}", tex, error))
+  }
+
+  #[test]
+  fn fractions() {
+    assert_eq!(
+      render(r"\frac{a}{b}"),
+      "<mrow><mfrac><mi>a</mi><mi>b</mi></mfrac></mrow>"
+    );
+    assert_eq!(render(r"\frac12"), "<mrow><mfrac><mn>1</mn><mn>2</mn></mfrac></mrow>");
+    assert_eq!(
+      render(r"\dfrac{x+1}{2}"),
+      r#"<mrow><mstyle displaystyle="true" scriptlevel="0"><mfrac><mrow><mi>x</mi><mo>+</mo><mn>1</mn></mrow><mn>2</mn></mfrac></mstyle></mrow>"#
+    );
+    assert_eq!(
+      render(r"{a \over b} + c"),
+      "<mrow><mfrac><mrow><mi>a</mi></mrow><mrow><mi>b</mi></mrow></mfrac><mo>+</mo><mi>c</mi></mrow>"
+    );
+    assert_eq!(render(r"a \over b"), render(r"{a \over b}"));
+  }
+
+  #[test]
+  fn scripts() {
+    assert_eq!(render("x^2"), "<mrow><msup><mi>x</mi><mn>2</mn></msup></mrow>");
+    assert_eq!(
+      render("x_i^{n+1}"),
+      "<mrow><msubsup><mi>x</mi><mi>i</mi><mrow><mi>n</mi><mo>+</mo><mn>1</mn></mrow></msubsup></mrow>"
+    );
+    assert_eq!(render("f'"), "<mrow><msup><mi>f</mi><mo>′</mo></msup></mrow>");
+    assert!(render(r"\sum_{k=1}^{n} k").starts_with("<mrow><munderover>"));
+  }
+
+  #[test]
+  fn roots() {
+    assert_eq!(render(r"\sqrt{x}"), "<mrow><msqrt><mi>x</mi></msqrt></mrow>");
+    assert_eq!(
+      render(r"\sqrt[3]{x}"),
+      "<mrow><mroot><mi>x</mi><mrow><mn>3</mn></mrow></mroot></mrow>"
+    );
+  }
+
+  #[test]
+  fn left_right() {
+    assert_eq!(
+      render(r"\left( x \right)"),
+      r#"<mrow><mrow><mo fence="true" stretchy="true" symmetric="true">(</mo><mrow><mi>x</mi></mrow><mo fence="true" stretchy="true" symmetric="true">)</mo></mrow></mrow>"#
+    );
+    assert!(render(r"\left. \frac{a}{b} \right|").contains("<mfrac>"));
+  }
+
+  #[test]
+  fn environments() {
+    assert_eq!(
+      render(r"\begin{pmatrix} a & b \\ c & d \end{pmatrix}"),
+      r#"<mrow><mrow><mo fence="true" stretchy="true" symmetric="true">(</mo><mtable><mtr><mtd><mrow><mi>a</mi></mrow></mtd><mtd><mrow><mi>b</mi></mrow></mtd></mtr><mtr><mtd><mrow><mi>c</mi></mrow></mtd><mtd><mrow><mi>d</mi></mrow></mtd></mtr></mtable><mo fence="true" stretchy="true" symmetric="true">)</mo></mrow></mrow>"#
+    );
+    // A trailing `\\` doesn't add an empty row
+    assert_eq!(
+      render(r"\begin{aligned} x &= 1 \\ \end{aligned}"),
+      render(r"\begin{aligned} x &= 1 \end{aligned}")
+    );
+  }
+
+  #[test]
+  fn errors() {
+    for (tex, error) in [
+      (r"\frac{a}", "missing argument for `\\frac`"),
+      ("x^2^3", "double superscript (use braces, e.g. `x^{a^b}`)"),
+      ("{x", "missing `}`"),
+      ("x}", "unexpected `}` without a matching `{`"),
+      ("a & b", "`&` is only allowed inside an environment such as `aligned`"),
+      (r"a \\ b", "`\\\\` is only allowed inside an environment such as `aligned`"),
+      (r"\\", "`\\\\` is only allowed inside an environment such as `aligned`"),
+      (r"\left( x", "`\\left` without a matching `\\right`"),
+      (r"x \right)", "`\\right` without a matching `\\left`"),
+      (r"\begin{matrix} a", "missing `\\end{matrix}`"),
+      (r"\begin{matrix} a \end{pmatrix}", "`\\begin{matrix}` is closed by `\\end{pmatrix}`"),
+      (r"\begin{foo} a \end{foo}", "unknown environment `foo`"),
+      (r"\foo", "unknown command `\\foo`"),
+      (r"a \over b \over c", "more than one `\\over` in a group (use `\\frac`)"),
+    ] {
+      assert_eq!(parse(tex), Err(error.to_string()), "{}", tex);
+    }
+  }
+
+  #[test]
+  fn keeps_the_source_as_an_annotation() {
+    let ml = to_mathml("a < b", true).unwrap();
+    assert!(ml.starts_with(r#"<math xmlns="http://www.w3.org/1998/Math/MathML" display="block">"#));
+    assert!(ml.contains(r#"<annotation encoding="application/x-tex">a &lt; b</annotation>"#));
+  }
+}
@@ -0,0 +1,284 @@
+// Open Graph card images: the post title, date, tags and site name drawn onto a PNG
+
+use crate::images::stable_hash;
+use crate::posts::{is_cjk, load_posts, Post};
+use ab_glyph::{point, Font, FontVec, PxScale, ScaleFont};
+use image::{Rgb, RgbImage};
+use std::path::PathBuf;
+use std::sync::LazyLock;
+
+// Change this to your blog's name
+const SITE_NAME: &str = "Your Blog Name";
+
+pub const WIDTH: u32 = 1200;
+pub const HEIGHT: u32 = 630;
+const PADDING: f32 = 80.0;
+
+// Colors of the dark theme in style/main.scss
+const BACKGROUND: Rgb<u8> = Rgb([0x1a, 0x1a, 0x1a]);
+const TEXT: Rgb<u8> = Rgb([0xe8, 0xe8, 0xe8]);
+const TEXT_SECONDARY: Rgb<u8> = Rgb([0xa8, 0xa8, 0xa8]);
+const ACCENT: Rgb<u8> = Rgb([0x7e, 0xe7, 0x87]);
+
+/// Bump when the layout changes so cached cards are redrawn
+const LAYOUT_VERSION: u32 = 1;
+
+/// The fonts from `BLOG_OG_FONTS`, loaded once
+static FONTS: LazyLock<Result<Fonts, String>> = LazyLock::new(Fonts::load);
+
+struct Fonts {
+  fonts: Vec<FontVec>,
+}
+
+impl Fonts {
+  fn load() -> Result<Self, String> {
+    let paths = &crate::config::site_config().og_fonts;
+    let mut fonts = Vec::new();
+    for path in paths {
+      let font = std::fs::read(path)
+        .map_err(|e| e.to_string())
+        .and_then(|data| FontVec::try_from_vec_and_index(data, 0).map_err(|e| e.to_string()));
+      match font {
+        Ok(font) => fonts.push(font),
+        Err(e) => eprintln!("Error loading font {}: {}", path.display(), e),
+      }
+    }
+
+    if fonts.is_empty() {
+      return Err("no usable font, set BLOG_OG_FONTS to one or more .ttf files".to_string());
+    }
+    Ok(Fonts { fonts })
+  }
+
+  /// The first font with a glyph for `c`, so a Latin font can be backed by a CJK one
+  fn for_char(&self, c: char) -> &FontVec {
+    self
+      .fonts
+      .iter()
+      .find(|font| font.glyph_id(c).0 != 0)
+      .unwrap_or(&self.fonts[0])
+  }
+
+  fn advance(&self, c: char, size: f32) -> f32 {
+    let font = self.for_char(c).as_scaled(PxScale::from(size));
+    font.h_advance(font.glyph_id(c))
+  }
+
+  fn width(&self, text: &str, size: f32) -> f32 {
+    text.chars().map(|c| self.advance(c, size)).sum()
+  }
+
+  /// Draw `text` with its baseline at `y`, returning the x where it ends
+  fn draw(
+    &self,
+    image: &mut RgbImage,
+    text: &str,
+    x: f32,
+    y: f32,
+    size: f32,
+    color: Rgb<u8>,
+  ) -> f32 {
+    let mut x = x;
+    for c in text.chars() {
+      let font = self.for_char(c);
+      let scaled = font.as_scaled(PxScale::from(size));
+      let glyph = scaled
+        .glyph_id(c)
+        .with_scale_and_position(size, point(x, y));
+      x += scaled.h_advance(glyph.id);
+
+      let Some(outline) = font.outline_glyph(glyph) else {
+        continue;
+      };
+      let bounds = outline.px_bounds();
+      outline.draw(|gx, gy, coverage| {
+        let px = bounds.min.x as i32 + gx as i32;
+        let py = bounds.min.y as i32 + gy as i32;
+        if px < 0 || py < 0 || px >= WIDTH as i32 || py >= HEIGHT as i32 {
+          return;
+        }
+        let pixel = image.get_pixel_mut(px as u32, py as u32);
+        let alpha = coverage.clamp(0.0, 1.0);
+        for channel in 0..3 {
+          let blended = pixel[channel] as f32 * (1.0 - alpha) + color[channel] as f32 * alpha;
+          pixel[channel] = blended.round() as u8;
+        }
+      });
+    }
+    x
+  }
+
+  /// Break `text` into lines no wider than `max_width`, at spaces or between CJK characters
+  fn wrap(&self, text: &str, size: f32, max_width: f32) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    let mut width = 0.0;
+    // Byte offset in `line` of the last place it may be broken
+    let mut last_break = None;
+
+    for c in text.chars() {
+      let advance = self.advance(c, size);
+      if width + advance > max_width && !line.is_empty() {
+        let rest = match last_break.filter(|&index| index > 0) {
+          Some(index) => line.split_off(index),
+          None => String::new(),
+        };
+        lines.push(line.trim_end().to_string());
+        line = rest.trim_start().to_string();
+        width = self.width(&line, size);
+        last_break = None;
+      }
+      if c.is_whitespace() && line.is_empty() {
+        continue;
+      }
+
+      if is_cjk(c) {
+        last_break = Some(line.len());
+      }
+      line.push(c);
+      width += advance;
+      if c.is_whitespace() || is_cjk(c) {
+        last_break = Some(line.len());
+      }
+    }
+    if !line.trim().is_empty() {
+      lines.push(line.trim_end().to_string());
+    }
+    lines
+  }
+
+  /// Shorten `text` with an ellipsis if it doesn't fit in `max_width`
+  fn truncate(&self, text: &str, size: f32, max_width: f32) -> String {
+    if self.width(text, size) <= max_width {
+      text.to_string()
+    } else {
+      self.ellipsize(text, size, max_width)
+    }
+  }
+
+  /// `text` with an ellipsis, shortened until both fit in `max_width`
+  fn ellipsize(&self, text: &str, size: f32, max_width: f32) -> String {
+    let mut text = text.trim_end().to_string();
+    while !text.is_empty() && self.width(&text, size) + self.advance('…', size) > max_width {
+      text.pop();
+    }
+    format!("{}…", text.trim_end())
+  }
+}
+
+/// Whether cards can be drawn, i.e. a font from `BLOG_OG_FONTS` loaded
+pub fn cards_available() -> bool {
+  FONTS.is_ok()
+}
+
+/// The card's PNG for a visible post, drawn on first request and cached on disk.
+///
+/// Drawing and encoding take a while, so call this from a blocking task.
+pub fn card_png(slug: &str) -> Option<Vec<u8>> {
+  let post = load_posts().into_iter().find(|post| post.slug == slug)?;
+  let fonts = match FONTS.as_ref() {
+    Ok(fonts) => fonts,
+    Err(e) => {
+      eprintln!("Error drawing Open Graph image for {}: {}", slug, e);
+      return None;
+    }
+  };
+
+  let path = cache_path(&post);
+  if let Ok(png) = std::fs::read(&path) {
+    return Some(png);
+  }
+
+  let mut png = Vec::new();
+  let encoded = image::DynamicImage::ImageRgb8(draw_card(fonts, &post))
+    .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png);
+  if let Err(e) = encoded {
+    eprintln!("Error encoding Open Graph image for {}: {}", slug, e);
+    return None;
+  }
+
+  // A failed write only costs redrawing the card next time
+  let written = path
+    .parent()
+    .map_or(Ok(()), std::fs::create_dir_all)
+    .and_then(|_| std::fs::write(&path, &png));
+  if let Err(e) = written {
+    eprintln!("Error caching Open Graph image {}: {}", path.display(), e);
+  }
+  Some(png)
+}
+
+/// Cards are named by a hash of everything drawn on them
+fn cache_path(post: &Post) -> PathBuf {
+  let config = crate::config::site_config();
+  let mut parts = vec![
+    LAYOUT_VERSION.to_string(),
+    SITE_NAME.to_string(),
+    post.metadata.title.clone(),
+    post.metadata.display_date(),
+    post.metadata.tags.len().to_string(),
+  ];
+  parts.extend(post.metadata.tags.iter().cloned());
+  parts.extend(config.og_fonts.iter().map(|path| path.display().to_string()));
+  let parts: Vec<&[u8]> = parts.iter().map(|part| part.as_bytes()).collect();
+  config
+    .image_cache_dir
+    .join("og")
+    .join(format!("{:016x}.png", stable_hash(&parts)))
+}
+
+fn draw_card(fonts: &Fonts, post: &Post) -> RgbImage {
+  let mut image = RgbImage::from_pixel(WIDTH, HEIGHT, BACKGROUND);
+  let max_width = WIDTH as f32 - 2.0 * PADDING;
+
+  // Accent bar along the top edge
+  for y in 0..12 {
+    for x in 0..WIDTH {
+      image.put_pixel(x, y, ACCENT);
+    }
+  }
+
+  fonts.draw(&mut image, SITE_NAME, PADDING, 140.0, 36.0, ACCENT);
+
+  // The largest size that fits the title in three lines, ellipsized at the smallest
+  let title = post.metadata.title.trim();
+  let (size, mut lines) = [76.0, 64.0, 54.0]
+    .iter()
+    .map(|&size| (size, fonts.wrap(title, size, max_width)))
+    .find(|(_, lines)| lines.len() <= 3)
+    .unwrap_or_else(|| (54.0, fonts.wrap(title, 54.0, max_width)));
+  if lines.len() > 3 {
+    lines.truncate(3);
+    lines[2] = fonts.ellipsize(&lines[2], size, max_width);
+  }
+  let line_height = size * 1.25;
+  let mut baseline = 240.0 + size;
+  for line in &lines {
+    fonts.draw(&mut image, line, PADDING, baseline, size, TEXT);
+    baseline += line_height;
+  }
+
+  // Date and tags along the bottom
+  let footer = HEIGHT as f32 - PADDING;
+  let x = fonts.draw(
+    &mut image,
+    &post.metadata.display_date(),
+    PADDING,
+    footer,
+    32.0,
+    TEXT_SECONDARY,
+  );
+  let tags: Vec<String> = post
+    .metadata
+    .tags
+    .iter()
+    .map(|tag| format!("#{}", tag))
+    .collect();
+  if !tags.is_empty() {
+    let x = x + fonts.advance(' ', 32.0) * 2.0;
+    let tags = fonts.truncate(&tags.join("  "), 32.0, WIDTH as f32 - PADDING - x);
+    fonts.draw(&mut image, &tags, x, footer, 32.0, ACCENT);
+  }
+
+  image
+}
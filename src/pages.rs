@@ -0,0 +1,273 @@
+// Standalone pages (about, uses, now, ...) written in markdown under `pages/`
+
+use crate::front_matter;
+use crate::posts::{invalid_math, is_valid_slug, markdown_to_html, LinkReference, PostError};
+use crate::wiki_links::WikiIndex;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// First path segments taken by the app's own routes and files
+pub const RESERVED_SLUGS: &[&str] = &[
+  "archive",
+  "posts",
+  "tags",
+  "series",
+  "authors",
+  "pkg",
+  "api",
+  "rss.xml",
+  "sitemap.xml",
+  "robots.txt",
+  "highlight.css",
+  "favicon.ico",
+  "_images",
+  "og",
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct PageMetadata {
+  pub title: String,
+  #[serde(default)]
+  pub description: String,
+  /// URL slug; defaults to the filename without extension
+  #[serde(default)]
+  pub slug: Option<String>,
+  /// Position in the header navigation; pages without one are only reachable by URL
+  #[serde(default)]
+  pub nav_order: Option<u32>,
+  /// Shorter label for the header navigation; defaults to the title
+  #[serde(default)]
+  pub nav_title: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Page {
+  pub slug: String,
+  pub metadata: PageMetadata,
+  pub content: String,
+  #[serde(skip)]
+  warnings: Vec<PostError>, // Problems that don't stop the page from loading
+  #[serde(skip)]
+  source: PathBuf, // The markdown file, for reporting problems
+  #[serde(skip)]
+  links: Vec<LinkReference>, // Link and image URLs in the content, checked after loading
+}
+
+/// Header navigation entry for a page
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct NavLink {
+  pub slug: String,
+  pub title: String,
+}
+
+impl Page {
+  /// Parse a `pages/<slug>.md` file, resolving `[[wiki links]]` to posts with `wiki`
+  pub fn from_markdown(
+    path: &Path,
+    slug: String,
+    markdown_content: &str,
+    wiki: &WikiIndex,
+  ) -> Result<Self, PostError> {
+    let front_matter =
+      front_matter::split(markdown_content).map_err(|e| PostError::front_matter(path, e))?;
+    let metadata: PageMetadata =
+      front_matter::parse(&front_matter).map_err(|e| PostError::front_matter(path, e))?;
+
+    let slug = metadata
+      .slug
+      .as_deref()
+      .map(str::trim)
+      .unwrap_or(&slug)
+      .to_string();
+    if !is_valid_slug(&slug) {
+      return Err(PostError::invalid_field(
+        path,
+        "slug",
+        format!(
+          "`{}` is not a valid slug (use letters, digits, `-`, `_` and `.` only)",
+          slug
+        ),
+      ));
+    }
+    if RESERVED_SLUGS.contains(&slug.as_str()) {
+      return Err(PostError::invalid_field(
+        path,
+        "slug",
+        format!("`/{}` is already used by the site itself", slug),
+      ));
+    }
+
+    let body = crate::shortcodes::expand_body(path, &front_matter)?;
+    let rendered = markdown_to_html(&body.markdown, None, wiki);
+
+    Ok(Page {
+      slug,
+      metadata,
+      content: rendered.html,
+      warnings: invalid_math(path, &body, rendered.math_errors),
+      source: path.to_path_buf(),
+      links: body.link_lines(rendered.links),
+    })
+  }
+
+  pub fn url(&self) -> String {
+    format!("https://your-domain.com/{}", self.slug)
+  }
+
+  /// The markdown file this page was loaded from
+  pub fn source(&self) -> &Path {
+    &self.source
+  }
+
+  /// Link and image URLs in the content, with their line in [`Page::source`]
+  #[cfg(feature = "ssr")]
+  pub(crate) fn links(&self) -> &[LinkReference] {
+    &self.links
+  }
+}
+
+#[cfg(feature = "ssr")]
+#[derive(Default)]
+struct LoadedPages {
+  pages: Vec<Page>,
+  errors: Vec<PostError>,
+  /// Problems in pages that loaded anyway, such as formulas that aren't valid TeX
+  warnings: Vec<PostError>,
+  /// Post titles the pages' `[[wiki links]]` were resolved with
+  wiki: std::sync::Arc<WikiIndex>,
+}
+
+#[cfg(feature = "ssr")]
+fn load_pages_cached() -> std::sync::Arc<LoadedPages> {
+  use std::sync::{Arc, LazyLock, Mutex};
+
+  static PAGES: LazyLock<Mutex<Option<Arc<LoadedPages>>>> = LazyLock::new(|| Mutex::new(None));
+
+  // Render again when the posts were reloaded, e.g. because a scheduled post went live
+  let wiki = crate::posts::load_wiki_index();
+  if let Ok(cache) = PAGES.lock() {
+    if let Some(ref cached) = *cache {
+      if Arc::ptr_eq(&cached.wiki, &wiki) {
+        return cached.clone();
+      }
+    }
+  }
+
+  let loaded = Arc::new(read_pages_dir(Path::new("pages"), wiki));
+  if let Ok(mut cache) = PAGES.lock() {
+    *cache = Some(loaded.clone());
+  }
+
+  loaded
+}
+
+#[cfg(feature = "ssr")]
+fn read_pages_dir(pages_dir: &Path, wiki: std::sync::Arc<WikiIndex>) -> LoadedPages {
+  use std::collections::HashMap;
+  use std::fs;
+
+  let mut loaded = LoadedPages {
+    wiki,
+    ..LoadedPages::default()
+  };
+
+  let mut paths: Vec<PathBuf> = match fs::read_dir(pages_dir) {
+    Ok(entries) => entries.flatten().map(|entry| entry.path()).collect(),
+    Err(_) => return loaded,
+  };
+  paths.sort();
+
+  // Slug -> file that claimed it first
+  let mut claimed: HashMap<String, PathBuf> = HashMap::new();
+
+  for path in paths {
+    if path.extension().and_then(|s| s.to_str()) != Some("md") {
+      continue;
+    }
+
+    let filename = path
+      .file_stem()
+      .and_then(|s| s.to_str())
+      .unwrap_or("")
+      .to_string();
+
+    let result = fs::read_to_string(&path)
+      .map_err(|e| PostError::Io {
+        path: path.clone(),
+        message: e.to_string(),
+      })
+      .and_then(|content| Page::from_markdown(&path, filename, &content, &loaded.wiki))
+      .and_then(|page| match claimed.get(&page.slug) {
+        Some(other) => Err(PostError::DuplicateSlug {
+          path: path.clone(),
+          url: format!("/{}", page.slug),
+          other: other.clone(),
+        }),
+        None => Ok(page),
+      });
+
+    match result {
+      Ok(mut page) => {
+        loaded.warnings.append(&mut page.warnings);
+        claimed.insert(page.slug.clone(), path.clone());
+        loaded.pages.push(page);
+      }
+      Err(e) => {
+        eprintln!("Error loading page: {}", e);
+        loaded.errors.push(e);
+      }
+    }
+  }
+
+  loaded
+}
+
+#[cfg(feature = "ssr")]
+pub fn load_pages() -> Vec<Page> {
+  load_pages_cached().pages.clone()
+}
+
+#[cfg(feature = "ssr")]
+pub fn load_page(slug: &str) -> Option<Page> {
+  load_pages_cached()
+    .pages
+    .iter()
+    .find(|page| page.slug == slug)
+    .cloned()
+}
+
+/// Pages that failed to load, sorted by file path
+#[cfg(feature = "ssr")]
+pub fn load_page_errors() -> Vec<PostError> {
+  load_pages_cached().errors.clone()
+}
+
+/// Problems in pages that still loaded, sorted by file path, for `check` and the startup log
+#[cfg(feature = "ssr")]
+pub fn load_page_warnings() -> Vec<PostError> {
+  load_pages_cached().warnings.clone()
+}
+
+/// Pages listed in the header, in `nav_order`
+#[cfg(feature = "ssr")]
+pub fn nav_links() -> Vec<NavLink> {
+  let loaded = load_pages_cached();
+  let mut pages: Vec<&Page> = loaded
+    .pages
+    .iter()
+    .filter(|page| page.metadata.nav_order.is_some())
+    .collect();
+  pages.sort_by_key(|page| (page.metadata.nav_order, page.slug.clone()));
+
+  pages
+    .into_iter()
+    .map(|page| NavLink {
+      slug: page.slug.clone(),
+      title: page
+        .metadata
+        .nav_title
+        .clone()
+        .unwrap_or_else(|| page.metadata.title.clone()),
+    })
+    .collect()
+}
@@ -1,31 +1,175 @@
+use crate::authors::Author;
+use crate::escape::escape_html;
+use crate::front_matter::{self, FrontMatterError};
+use crate::images::{self, ResponsiveImage};
+use crate::shortcodes::{self, Body};
+use crate::wiki_links::WikiIndex;
+use chrono::{DateTime, FixedOffset, Utc};
 use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::{Path, PathBuf};
 
-/// Extract the first image URL from markdown content
-fn extract_thumbnail(markdown: &str) -> Option<String> {
-  for line in markdown.lines() {
-    let trimmed = line.trim();
-    // Match markdown image: ![alt](url)
-    if trimmed.starts_with("!") && trimmed.contains("](") {
-      if let Some(start) = trimmed.find("](") {
-        if let Some(end) = trimmed[start + 2..].find(")") {
-          let url = &trimmed[start + 2..start + 2 + end];
-          return Some(url.to_string());
-        }
-      }
+/// Errors produced while turning a markdown file into a [`Post`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PostError {
+  /// The file could not be read from disk
+  Io { path: PathBuf, message: String },
+  /// The file does not start with a `---` (YAML) or `+++` (TOML) front matter block
+  MissingFrontMatter { path: PathBuf },
+  /// The opening front matter delimiter has no matching closing delimiter
+  UnterminatedFrontMatter { path: PathBuf, line: usize },
+  /// The front matter is not valid YAML/TOML or does not match [`PostMetadata`]
+  InvalidFrontMatter {
+    path: PathBuf,
+    line: Option<usize>,
+    column: Option<usize>,
+    field: Option<String>,
+    message: String,
+  },
+  /// The post's slug or one of its aliases is already claimed by another post
+  DuplicateSlug {
+    path: PathBuf,
+    url: String,
+    other: PathBuf,
+  },
+  /// A shortcode in the body is unknown or was given invalid arguments
+  InvalidShortcode {
+    path: PathBuf,
+    line: usize,
+    message: String,
+  },
+  /// A `$...$` or `$$...$$` formula is not valid TeX; the post still loads and shows the source
+  InvalidMath {
+    path: PathBuf,
+    line: usize,
+    message: String,
+  },
+  /// An internal link, anchor or local file reference that doesn't resolve, found by
+  /// [`crate::links::check_links`]
+  BrokenLink {
+    path: PathBuf,
+    line: usize,
+    url: String,
+    message: String,
+  },
+}
+
+impl PostError {
+  pub(crate) fn front_matter(path: &Path, error: FrontMatterError) -> Self {
+    let path = path.to_path_buf();
+    match error {
+      FrontMatterError::Missing => PostError::MissingFrontMatter { path },
+      FrontMatterError::Unterminated { line } => PostError::UnterminatedFrontMatter { path, line },
+      FrontMatterError::Invalid {
+        line,
+        column,
+        field,
+        message,
+      } => PostError::InvalidFrontMatter {
+        path,
+        line,
+        column,
+        field,
+        message,
+      },
     }
-    // Match HTML img tag: <img src="url"
-    if trimmed.contains("<img") && trimmed.contains("src=") {
-      if let Some(start) = trimmed.find("src=\"") {
-        if let Some(end) = trimmed[start + 5..].find("\"") {
-          let url = &trimmed[start + 5..start + 5 + end];
-          return Some(url.to_string());
+  }
+
+  /// A front matter value that parsed fine but failed validation
+  pub(crate) fn invalid_field(path: &Path, field: &str, message: String) -> Self {
+    PostError::InvalidFrontMatter {
+      path: path.to_path_buf(),
+      line: None,
+      column: None,
+      field: Some(field.to_string()),
+      message,
+    }
+  }
+
+  pub fn path(&self) -> &Path {
+    match self {
+      PostError::Io { path, .. }
+      | PostError::MissingFrontMatter { path }
+      | PostError::UnterminatedFrontMatter { path, .. }
+      | PostError::InvalidFrontMatter { path, .. }
+      | PostError::DuplicateSlug { path, .. }
+      | PostError::InvalidShortcode { path, .. }
+      | PostError::InvalidMath { path, .. }
+      | PostError::BrokenLink { path, .. } => path,
+    }
+  }
+}
+
+impl fmt::Display for PostError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      PostError::Io { path, message } => write!(f, "{}: {}", path.display(), message),
+      PostError::MissingFrontMatter { path } => write!(
+        f,
+        "{}:1: missing front matter (the file must start with a `---` or `+++` line)",
+        path.display()
+      ),
+      PostError::UnterminatedFrontMatter { path, line } => write!(
+        f,
+        "{}:{}: front matter is never closed (expected a matching `---` or `+++` line)",
+        path.display(),
+        line
+      ),
+      PostError::InvalidFrontMatter {
+        path,
+        line,
+        column,
+        field,
+        message,
+      } => {
+        write!(f, "{}", path.display())?;
+        if let Some(line) = line {
+          write!(f, ":{}", line)?;
+          if let Some(column) = column {
+            write!(f, ":{}", column)?;
+          }
+        }
+        match field {
+          Some(field) => write!(f, ": invalid front matter field `{}`: {}", field, message),
+          None => write!(f, ": invalid front matter: {}", message),
         }
       }
+      PostError::DuplicateSlug { path, url, other } => write!(
+        f,
+        "{}: {} is already used by {}",
+        path.display(),
+        url,
+        other.display()
+      ),
+      PostError::InvalidShortcode {
+        path,
+        line,
+        message,
+      } => write!(f, "{}:{}: {}", path.display(), line, message),
+      PostError::InvalidMath {
+        path,
+        line,
+        message,
+      } => write!(f, "{}:{}: invalid math: {}", path.display(), line, message),
+      PostError::BrokenLink {
+        path,
+        line,
+        url,
+        message,
+      } => write!(
+        f,
+        "{}:{}: broken link `{}`: {}",
+        path.display(),
+        line,
+        url,
+        message
+      ),
     }
   }
-  None
 }
 
+impl std::error::Error for PostError {}
+
 /// Extract plain text from markdown content (removes formatting)
 fn extract_text_preview(markdown: &str, max_chars: usize) -> String {
   let mut result = String::new();
@@ -46,6 +190,11 @@ fn extract_text_preview(markdown: &str, max_chars: usize) -> String {
     if line.trim().starts_with("!") && line.contains("](") {
       continue;
     }
+
+    // Skip raw HTML blocks, e.g. expanded shortcodes
+    if line.trim().starts_with('<') {
+      continue;
+    }
     
     // Skip empty lines
     let trimmed = line.trim();
@@ -55,7 +204,7 @@ fn extract_text_preview(markdown: &str, max_chars: usize) -> String {
     
     // Remove markdown formatting
     let clean_line = trimmed
-      .trim_start_matches(|c| c == '#' || c == ' ')
+      .trim_start_matches(['#', ' '])
       .replace("**", "")
       .replace("*", "")
       .replace("__", "")
@@ -81,16 +230,430 @@ fn extract_text_preview(markdown: &str, max_chars: usize) -> String {
   }
 }
 
+/// Words per minute for space-separated scripts
+const WORDS_PER_MINUTE: usize = 230;
+/// Characters per minute for Hangul, kana and Han text
+const CJK_CHARS_PER_MINUTE: usize = 500;
+
+/// Length of a post's prose, leaving out code blocks
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ReadingTime {
+  /// Space-separated words plus CJK characters, each of which counts as one word
+  pub words: usize,
+  /// Estimated reading time, rounded up to whole minutes
+  pub minutes: usize,
+}
+
+impl ReadingTime {
+  fn from_markdown(markdown: &str) -> Self {
+    use pulldown_cmark::{Event, Parser, Tag, TagEnd};
+
+    let mut words = 0;
+    let mut cjk_chars = 0;
+    let mut in_word = false;
+    let mut in_code_block = false;
+
+    for event in Parser::new_ext(markdown, markdown_options()) {
+      let text = match event {
+        Event::Start(Tag::CodeBlock(_)) => {
+          in_code_block = true;
+          continue;
+        }
+        Event::End(TagEnd::CodeBlock) => {
+          in_code_block = false;
+          continue;
+        }
+        Event::Text(text) | Event::Code(text) if !in_code_block => text,
+        Event::End(_) | Event::SoftBreak | Event::HardBreak => {
+          in_word = false;
+          continue;
+        }
+        _ => continue,
+      };
+
+      // Hangul and Han runs are counted per character, not per whitespace-separated token
+      for c in text.chars() {
+        if is_cjk(c) {
+          cjk_chars += 1;
+          in_word = false;
+        } else if c.is_whitespace() {
+          in_word = false;
+        } else if c.is_alphanumeric() && !in_word {
+          words += 1;
+          in_word = true;
+        }
+      }
+    }
+
+    let minutes = (words * CJK_CHARS_PER_MINUTE + cjk_chars * WORDS_PER_MINUTE)
+      .div_ceil(WORDS_PER_MINUTE * CJK_CHARS_PER_MINUTE)
+      .max(1);
+    ReadingTime {
+      words: words + cjk_chars,
+      minutes,
+    }
+  }
+
+  /// ISO 8601 duration for `timeRequired`
+  pub fn iso_duration(&self) -> String {
+    format!("PT{}M", self.minutes)
+  }
+}
+
+pub(crate) fn is_cjk(c: char) -> bool {
+  matches!(c,
+    '\u{1100}'..='\u{11FF}' // Hangul Jamo
+    | '\u{3040}'..='\u{30FF}' // Hiragana and Katakana
+    | '\u{3130}'..='\u{318F}' // Hangul Compatibility Jamo
+    | '\u{3400}'..='\u{4DBF}' // CJK Extension A
+    | '\u{4E00}'..='\u{9FFF}' // CJK Unified Ideographs
+    | '\u{AC00}'..='\u{D7AF}' // Hangul Syllables
+    | '\u{F900}'..='\u{FAFF}' // CJK Compatibility Ideographs
+  )
+}
+
+/// A heading in a post's table of contents
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TocEntry {
+  /// Anchor id of the heading
+  pub id: String,
+  /// Plain text of the heading
+  pub title: String,
+  /// Heading level, 1 to 6
+  pub level: u8,
+  /// Headings nested below this one
+  pub children: Vec<TocEntry>,
+}
+
+impl TocEntry {
+  /// Number of entries in this subtree, including itself
+  pub fn count(&self) -> usize {
+    1 + self.children.iter().map(TocEntry::count).sum::<usize>()
+  }
+}
+
+/// Anchor id for a heading: lowercase letters and digits of any script, with runs of
+/// whitespace and punctuation turned into `-`
+pub(crate) fn heading_id(text: &str) -> String {
+  let mut id = String::new();
+  for c in text.trim().chars() {
+    if c.is_alphanumeric() || c == '_' {
+      id.extend(c.to_lowercase());
+    } else if !id.is_empty() && !id.ends_with('-') {
+      id.push('-');
+    }
+  }
+  let id = id.trim_end_matches('-');
+  if id.is_empty() {
+    "section".to_string()
+  } else {
+    id.to_string()
+  }
+}
+
+/// Nest a flat list of headings under the closest preceding heading of a higher level
+fn nest_toc(flat: Vec<TocEntry>) -> Vec<TocEntry> {
+  fn attach(entries: &mut Vec<TocEntry>, entry: TocEntry) {
+    match entries.last_mut() {
+      Some(last) if last.level < entry.level => attach(&mut last.children, entry),
+      _ => entries.push(entry),
+    }
+  }
+
+  let mut toc = Vec::new();
+  for entry in flat {
+    attach(&mut toc, entry);
+  }
+  toc
+}
+
+/// Give every heading a unique id and a hover anchor link, collecting the table of contents.
+///
+/// Explicit `{#id}` attributes are kept as written; generated ids get a `-1`, `-2`, ...
+/// suffix when the same heading text appears more than once.
+fn add_heading_anchors<'a>(
+  events: impl IntoIterator<Item = pulldown_cmark::Event<'a>>,
+) -> (Vec<pulldown_cmark::Event<'a>>, Vec<TocEntry>) {
+  use pulldown_cmark::{Event, Tag, TagEnd};
+  use std::collections::HashSet;
+
+  let events: Vec<Event> = events.into_iter().collect();
+  let mut used_ids: HashSet<String> = events
+    .iter()
+    .filter_map(|event| match event {
+      Event::Start(Tag::Heading { id: Some(id), .. }) => Some(id.to_string()),
+      _ => None,
+    })
+    .collect();
+
+  let mut output = Vec::with_capacity(events.len());
+  let mut flat_toc = Vec::new();
+  let mut events = events.into_iter();
+
+  while let Some(event) = events.next() {
+    let Event::Start(Tag::Heading {
+      level,
+      id,
+      classes,
+      attrs,
+    }) = event
+    else {
+      output.push(event);
+      continue;
+    };
+
+    let mut inner = Vec::new();
+    let mut title = String::new();
+    for event in events.by_ref() {
+      match &event {
+        Event::End(TagEnd::Heading(_)) => break,
+        Event::Text(text) | Event::Code(text) => title.push_str(text),
+        _ => {}
+      }
+      inner.push(event);
+    }
+    let title = title.trim().to_string();
+
+    let id = match id {
+      Some(id) => id.to_string(),
+      None => {
+        let base = heading_id(&title);
+        let mut candidate = base.clone();
+        let mut counter = 1;
+        while used_ids.contains(&candidate) {
+          candidate = format!("{}-{}", base, counter);
+          counter += 1;
+        }
+        used_ids.insert(candidate.clone());
+        candidate
+      }
+    };
+
+    let mut open_tag = format!("<{} id=\"{}\"", level, escape_html(&id));
+    if !classes.is_empty() {
+      let classes: Vec<&str> = classes.iter().map(|class| class.as_ref()).collect();
+      open_tag.push_str(&format!(" class=\"{}\"", escape_html(&classes.join(" "))));
+    }
+    for (name, value) in &attrs {
+      let value = value.as_deref().unwrap_or("");
+      open_tag.push_str(&format!(" {}=\"{}\"", escape_html(name), escape_html(value)));
+    }
+    open_tag.push('>');
+
+    output.push(Event::Html(open_tag.into()));
+    output.extend(inner);
+    output.push(Event::Html(
+      format!(
+        "<a class=\"heading-anchor\" href=\"#{}\" aria-label=\"Link to this section\">#</a></{}>",
+        escape_html(&id),
+        level
+      )
+      .into(),
+    ));
+
+    flat_toc.push(TocEntry {
+      id,
+      title,
+      level: level as u8,
+      children: Vec::new(),
+    });
+  }
+
+  (output, nest_toc(flat_toc))
+}
+
+/// A language posts can be written in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Language {
+  /// Code used in the `-<code>.md` filename suffix and the `lang` front matter key
+  pub code: &'static str,
+  /// Name shown in the language switcher
+  pub name: &'static str,
+  /// BCP 47 tag for `lang` attributes
+  pub html_lang: &'static str,
+  /// Open Graph locale
+  pub locale: &'static str,
+}
+
+/// Supported post languages. Add an entry here to support another suffix.
+pub const LANGUAGES: &[Language] = &[
+  Language {
+    code: "en",
+    name: "English",
+    html_lang: "en",
+    locale: "en_US",
+  },
+  Language {
+    code: "kr",
+    name: "한국어",
+    html_lang: "ko",
+    locale: "ko_KR",
+  },
+];
+
+/// Language of posts without a suffix or `lang` key
+pub const DEFAULT_LANGUAGE: &str = "en";
+
+pub fn language(code: &str) -> Option<&'static Language> {
+  LANGUAGES.iter().find(|language| language.code == code)
+}
+
+/// Split `2025-01-15-Title-en` into the shared article name and its language suffix
+fn split_language_suffix(stem: &str) -> (&str, Option<&'static Language>) {
+  stem
+    .rsplit_once('-')
+    .and_then(|(base, suffix)| language(suffix).map(|language| (base, Some(language))))
+    .unwrap_or((stem, None))
+}
+
+/// Another language version of the same article
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Translation {
+  pub lang: String,
+  pub slug: String,
+  pub title: String,
+}
+
+/// One part of a series, as linked from the other parts
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SeriesPart {
+  pub slug: String,
+  pub title: String,
+}
+
+/// A post that links to the current one
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Backlink {
+  pub slug: String,
+  pub title: String,
+}
+
+/// Where a post sits within its series
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SeriesNav {
+  pub name: String,
+  /// URL segment of the series page (`/series/<slug>`)
+  pub slug: String,
+  /// 1-based position of the current post
+  pub position: usize,
+  /// All parts in reading order, including the current post
+  pub parts: Vec<SeriesPart>,
+}
+
+impl SeriesNav {
+  pub fn total(&self) -> usize {
+    self.parts.len()
+  }
+}
+
+/// URL segment for a series name: lowercase, with runs of other characters turned into `-`
+pub fn series_slug(name: &str) -> String {
+  name
+    .to_lowercase()
+    .split(|c: char| !c.is_alphanumeric())
+    .filter(|word| !word.is_empty())
+    .collect::<Vec<_>>()
+    .join("-")
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct PostMetadata {
   pub title: String,
-  pub date: String, // Full datetime for sorting and SEO (YYYY-MM-DDTHH:MM:SS)
-  #[serde(default)]
-  pub display_date: String, // Display date only (YYYY-MM-DD) for post cards
-  #[serde(default)]
-  pub display_datetime: String, // Display date and time (YYYY-MM-DD HH:MM) for post detail page
+  /// Publication time in the site timezone
+  #[serde(deserialize_with = "crate::dates::deserialize")]
+  pub date: DateTime<FixedOffset>,
+  /// Time of the last significant revision
+  #[serde(default, deserialize_with = "crate::dates::deserialize_option")]
+  pub updated: Option<DateTime<FixedOffset>>,
   pub tags: Vec<String>,
   pub description: String,
+  /// Drafts are only shown in preview mode
+  #[serde(default)]
+  pub draft: bool,
+  /// The post stays hidden until this moment has passed
+  #[serde(default, deserialize_with = "crate::dates::deserialize_option")]
+  pub publish_at: Option<DateTime<FixedOffset>>,
+  /// Unlisted posts are reachable by URL but left out of listings, feeds and the sitemap
+  #[serde(default)]
+  pub unlisted: bool,
+  /// Language code, taken from the filename suffix unless set explicitly
+  #[serde(default)]
+  pub lang: String,
+  /// Shared by all language versions of one article; defaults to the filename without suffix
+  #[serde(default)]
+  pub translation_key: String,
+  /// URL slug; defaults to the filename without extension
+  #[serde(default)]
+  pub slug: Option<String>,
+  /// Former slugs or paths that redirect to this post
+  #[serde(default)]
+  pub aliases: Vec<String>,
+  /// Name of the multi-part series this post belongs to
+  #[serde(default)]
+  pub series: Option<String>,
+  /// Position within the series; parts without one follow, ordered by date
+  #[serde(default)]
+  pub series_order: Option<u32>,
+  /// Author ids from `authors.yaml`; defaults to the first registered author
+  #[serde(default)]
+  pub authors: Vec<String>,
+  /// Show the table of contents on the post page
+  #[serde(default = "default_true")]
+  pub toc: bool,
+  /// Image for the post card, the top of the post and link previews; relative paths
+  /// point into a page bundle
+  #[serde(default)]
+  pub cover: Option<String>,
+  /// Alt text for the cover image
+  #[serde(default)]
+  pub cover_alt: Option<String>,
+  /// Caption shown below the cover on the post page
+  #[serde(default)]
+  pub cover_caption: Option<String>,
+}
+
+fn default_true() -> bool {
+  true
+}
+
+impl PostMetadata {
+  /// Sort key placing series parts in reading order
+  pub fn series_sort_key(&self) -> (u32, DateTime<FixedOffset>) {
+    (self.series_order.unwrap_or(u32::MAX), self.date)
+  }
+
+  /// Date only (YYYY-MM-DD) for post cards
+  pub fn display_date(&self) -> String {
+    self.date.format("%Y-%m-%d").to_string()
+  }
+
+  /// Date and time (YYYY-MM-DD HH:MM) for the post detail page
+  pub fn display_datetime(&self) -> String {
+    self.date.format("%Y-%m-%d %H:%M").to_string()
+  }
+
+  /// Date of the last revision, when it differs from the publication date
+  pub fn display_updated(&self) -> Option<String> {
+    self
+      .updated
+      .filter(|updated| *updated > self.date)
+      .map(|updated| updated.format("%Y-%m-%d").to_string())
+  }
+
+  /// Last modification time for feeds and the sitemap
+  pub fn last_modified(&self) -> DateTime<FixedOffset> {
+    self.updated.map_or(self.date, |updated| updated.max(self.date))
+  }
+
+  /// Whether the post is publicly visible at `now`
+  pub fn is_published_at(&self, now: DateTime<Utc>) -> bool {
+    !self.draft && !self.is_scheduled_at(now)
+  }
+
+  /// Whether the post has a publish time that is still in the future
+  pub fn is_scheduled_at(&self, now: DateTime<Utc>) -> bool {
+    self.publish_at.is_some_and(|publish_at| publish_at > now)
+  }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -99,66 +662,543 @@ pub struct Post {
   pub metadata: PostMetadata,
   pub content: String,
   pub preview: String, // Text preview from content for SEO
-  pub thumbnail: Option<String>, // First image URL for thumbnails
+  pub thumbnail: Option<PostImage>, // Cover, or else the first image in the content
+  pub reading_time: ReadingTime,
+  pub toc: Vec<TocEntry>, // Headings in document order, nested by level
+  pub cover: Option<PostImage>, // From the `cover` front matter
+  #[serde(default)]
+  pub authors: Vec<Author>, // Resolved from `metadata.authors` when posts are loaded
+  #[serde(default)]
+  translations: Vec<Translation>, // Other language versions, linked when posts are loaded
+  #[serde(skip)]
+  redirect_from: Vec<String>, // Old URLs answered with a 301 to this post
+  #[serde(default)]
+  series_nav: Option<SeriesNav>, // Series navigation, linked when posts are loaded
+  #[serde(default)]
+  backlinks: Vec<Backlink>, // Listed posts linking to this one, linked when posts are loaded
+  #[serde(default)]
+  og_card: bool, // Whether `/og/<slug>.png` can be drawn, set when posts are loaded
+  #[serde(skip)]
+  bundle_dir: Option<PathBuf>, // Directory of a page bundle, whose files are served as assets
+  #[serde(skip)]
+  warnings: Vec<PostError>, // Problems that don't stop the post from loading
+  #[serde(skip)]
+  source: PathBuf, // The markdown file, for reporting problems
+  #[serde(skip)]
+  links: Vec<LinkReference>, // Link and image URLs in the content, checked after loading
+}
+
+/// An image standing for a post, with the alt text to show it with
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct PostImage {
+  pub image: ResponsiveImage,
+  pub alt: String,
+  pub caption: Option<String>,
+}
+
+/// Slugs end up in URLs and router paths, so keep them to a safe character set
+pub(crate) fn is_valid_slug(slug: &str) -> bool {
+  !slug.is_empty()
+    && slug
+      .chars()
+      .all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '.'))
+}
+
+/// Absolute URL path for an alias: `old-name` means `/posts/old-name`, `/2019/old.html` is kept
+fn alias_url(alias: &str) -> Option<String> {
+  let alias = alias.trim();
+  if let Some(absolute) = alias.strip_prefix('/') {
+    let absolute = absolute.trim_end_matches('/');
+    let valid = !absolute.is_empty() && absolute.split('/').all(is_valid_slug);
+    valid.then(|| format!("/{}", absolute))
+  } else {
+    is_valid_slug(alias).then(|| format!("/posts/{}", alias))
+  }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct PostSummary {
   pub slug: String,
   pub metadata: PostMetadata,
-  pub thumbnail: Option<String>, // First image URL for thumbnails
+  pub thumbnail: Option<PostImage>, // Cover, or else the first image in the content
+  pub reading_time: ReadingTime,
+  pub authors: Vec<Author>,
 }
 
 impl Post {
-  pub fn from_markdown(slug: String, markdown_content: &str) -> Result<Self, String> {
-    let parts: Vec<&str> = markdown_content.split("---").collect();
+  /// Parse a flat `posts/<slug>.md` file, resolving `[[wiki links]]` with `wiki`
+  pub fn from_markdown(
+    path: &Path,
+    slug: String,
+    markdown_content: &str,
+    wiki: &WikiIndex,
+  ) -> Result<Self, PostError> {
+    Self::parse(path, slug, markdown_content, None, wiki)
+  }
 
-    if parts.len() < 3 {
-      return Err("Invalid markdown format: missing front matter".to_string());
-    }
+  /// Parse the `index.md` of a `posts/<slug>/` bundle, whose sibling files are served
+  /// under the post's URL
+  pub fn from_bundle(
+    index_path: &Path,
+    slug: String,
+    markdown_content: &str,
+    wiki: &WikiIndex,
+  ) -> Result<Self, PostError> {
+    let bundle_dir = index_path.parent().unwrap_or(Path::new("."));
+    Self::parse(index_path, slug, markdown_content, Some(bundle_dir), wiki)
+  }
 
-    let front_matter = parts[1].trim();
-    let content = parts[2..].join("---").trim().to_string();
+  fn parse(
+    path: &Path,
+    slug: String,
+    markdown_content: &str,
+    bundle_dir: Option<&Path>,
+    wiki: &WikiIndex,
+  ) -> Result<Self, PostError> {
+    let front_matter =
+      front_matter::split(markdown_content).map_err(|e| PostError::front_matter(path, e))?;
+    let mut metadata: PostMetadata =
+      front_matter::parse(&front_matter).map_err(|e| PostError::front_matter(path, e))?;
+    let body = shortcodes::expand_body(path, &front_matter)?;
+    let content = &body.markdown;
 
-    let mut metadata: PostMetadata = serde_yaml::from_str(front_matter)
-      .map_err(|e| format!("Failed to parse front matter: {}", e))?;
+    let (article, suffix_language) = split_language_suffix(&slug);
+    if metadata.lang.is_empty() {
+      metadata.lang = suffix_language
+        .map(|language| language.code)
+        .unwrap_or(DEFAULT_LANGUAGE)
+        .to_string();
+    } else if language(&metadata.lang).is_none() {
+      let supported: Vec<&str> = LANGUAGES.iter().map(|language| language.code).collect();
+      return Err(PostError::invalid_field(
+        path,
+        "lang",
+        format!(
+          "unsupported language `{}`, expected one of: {}",
+          metadata.lang,
+          supported.join(", ")
+        ),
+      ));
+    }
+    if metadata.translation_key.is_empty() {
+      metadata.translation_key = article.to_string();
+    }
 
-    // Extract display_date (date only: YYYY-MM-DD) for post cards
-    metadata.display_date = if metadata.date.len() >= 10 {
-      metadata.date[..10].to_string()
-    } else {
-      metadata.date.clone()
+    // A custom slug replaces the filename; the old URL and any aliases redirect to it
+    let file_slug = slug;
+    let slug = match metadata.slug.as_deref().map(str::trim) {
+      Some(custom) if !is_valid_slug(custom) => {
+        return Err(PostError::invalid_field(
+          path,
+          "slug",
+          format!(
+            "`{}` is not a valid slug (use letters, digits, `-`, `_` and `.` only)",
+            custom
+          ),
+        ));
+      }
+      Some(custom) => custom.to_string(),
+      None => file_slug.clone(),
     };
 
-    // Format display_datetime (date and time: YYYY-MM-DD HH:MM) for post detail page
-    metadata.display_datetime = if metadata.date.len() >= 16 {
-      format!("{} {}", &metadata.date[..10], &metadata.date[11..16])
-    } else if metadata.date.len() >= 10 {
-      metadata.date[..10].to_string()
-    } else {
-      metadata.date.clone()
-    };
+    let mut redirect_from = Vec::new();
+    if slug != file_slug {
+      redirect_from.push(format!("/posts/{}", file_slug));
+    }
+    for alias in &metadata.aliases {
+      let url = alias_url(alias).ok_or_else(|| {
+        PostError::invalid_field(path, "aliases", format!("`{}` is not a valid alias", alias))
+      })?;
+      if url != format!("/posts/{}", slug) && !redirect_from.contains(&url) {
+        redirect_from.push(url);
+      }
+    }
 
-    let html_content = markdown_to_html(&content);
+    // Relative paths in a bundle point at files next to index.md
+    let asset_url = format!("/posts/{}/", slug);
+    let bundle = bundle_dir.map(|dir| BundleAssets {
+      url: &asset_url,
+      dir,
+    });
+
+    let rendered = markdown_to_html(content, bundle.as_ref(), wiki);
     
     // Extract preview text from markdown content (max 160 chars for SEO)
-    let preview = extract_text_preview(&content, 160);
+    let preview = extract_text_preview(&crate::wiki_links::plain_text(content, wiki), 160);
     
-    // Extract first image URL for thumbnail
-    let thumbnail = extract_thumbnail(&content);
+    let cover = metadata
+      .cover
+      .as_deref()
+      .map(str::trim)
+      .filter(|url| !url.is_empty())
+      .map(|url| PostImage {
+        image: images::resolve(
+          resolve_relative_url(url, bundle.as_ref().map(|bundle| bundle.url)),
+          bundle.as_ref(),
+        ),
+        alt: metadata.cover_alt.clone().unwrap_or_default(),
+        caption: metadata.cover_caption.clone(),
+      });
+    // Without a cover, cards show the first image of the post
+    let thumbnail = cover.clone().or_else(|| {
+      rendered.first_image.map(|(url, alt)| PostImage {
+        image: images::resolve(url, bundle.as_ref()),
+        alt,
+        caption: None,
+      })
+    });
+
+    let reading_time = ReadingTime::from_markdown(content);
 
     Ok(Post {
       slug,
       metadata,
-      content: html_content,
+      content: rendered.html,
       preview,
       thumbnail,
+      reading_time,
+      toc: rendered.toc,
+      cover,
+      authors: Vec::new(),
+      translations: Vec::new(),
+      redirect_from,
+      series_nav: None,
+      backlinks: Vec::new(),
+      og_card: false,
+      bundle_dir: bundle_dir.map(Path::to_path_buf),
+      warnings: invalid_math(path, &body, rendered.math_errors),
+      source: path.to_path_buf(),
+      links: body.link_lines(rendered.links),
+    })
+  }
+
+  /// Look up `metadata.authors` in the registry, crediting the default author when empty
+  #[cfg(feature = "ssr")]
+  fn resolve_authors(mut self, path: &Path, registry: &[Author]) -> Result<Self, PostError> {
+    if self.metadata.authors.is_empty() {
+      self.metadata.authors = registry.iter().take(1).map(|a| a.id.clone()).collect();
+    }
+
+    self.authors = self
+      .metadata
+      .authors
+      .iter()
+      .map(|id| {
+        registry.iter().find(|author| &author.id == id).cloned().ok_or_else(|| {
+          let known: Vec<&str> = registry.iter().map(|author| author.id.as_str()).collect();
+          PostError::invalid_field(
+            path,
+            "authors",
+            format!(
+              "unknown author `{}` (authors.yaml lists: {})",
+              id,
+              known.join(", ")
+            ),
+          )
+        })
+      })
+      .collect::<Result<_, _>>()?;
+
+    Ok(self)
+  }
+
+  /// The series this post is part of, with links to the other parts
+  pub fn series(&self) -> Option<&SeriesNav> {
+    self.series_nav.as_ref()
+  }
+
+  /// Old URLs (the filename-derived slug and `aliases`) that permanently redirect to this post
+  pub fn redirect_from(&self) -> &[String] {
+    &self.redirect_from
+  }
+
+  /// Other language versions of this article, e.g. to link the Korean copy from the English one
+  pub fn translations(&self) -> &[Translation] {
+    &self.translations
+  }
+
+  /// Listed posts that link to this one, newest first
+  pub fn backlinks(&self) -> &[Backlink] {
+    &self.backlinks
+  }
+
+  /// Whether a share card is served at `/og/<slug>.png` (no card font is installed otherwise)
+  pub fn has_og_card(&self) -> bool {
+    self.og_card
+  }
+
+  /// The markdown file this post was loaded from
+  pub fn source(&self) -> &Path {
+    &self.source
+  }
+
+  /// Link and image URLs in the content, with their line in [`Post::source`]
+  #[cfg(feature = "ssr")]
+  pub(crate) fn links(&self) -> &[LinkReference] {
+    &self.links
+  }
+}
+
+/// Fill in [`Post::translations`] from posts sharing a translation key
+#[cfg(feature = "ssr")]
+fn link_translations(posts: &mut [Post]) {
+  let versions: Vec<(String, Translation)> = posts
+    .iter()
+    .map(|post| {
+      (
+        post.metadata.translation_key.clone(),
+        Translation {
+          lang: post.metadata.lang.clone(),
+          slug: post.slug.clone(),
+          title: post.metadata.title.clone(),
+        },
+      )
+    })
+    .collect();
+
+  for post in posts.iter_mut() {
+    post.translations = versions
+      .iter()
+      .filter(|(key, version)| *key == post.metadata.translation_key && version.slug != post.slug)
+      .map(|(_, version)| version.clone())
+      .collect();
+  }
+}
+
+/// Fill in [`Post::series`] for listed posts, grouping parts by series and language
+#[cfg(feature = "ssr")]
+fn link_series(posts: &mut [Post]) {
+  use std::collections::BTreeMap;
+
+  // (series slug, language) -> indices of its parts
+  let mut series: BTreeMap<(String, String), Vec<usize>> = BTreeMap::new();
+  for (index, post) in posts.iter().enumerate() {
+    if let Some(name) = post.metadata.series.as_deref().filter(|_| !post.metadata.unlisted) {
+      series
+        .entry((series_slug(name), post.metadata.lang.clone()))
+        .or_default()
+        .push(index);
+    }
+  }
+
+  for ((slug, _), mut members) in series {
+    members.sort_by_key(|index| posts[*index].metadata.series_sort_key());
+    let parts: Vec<SeriesPart> = members
+      .iter()
+      .map(|index| SeriesPart {
+        slug: posts[*index].slug.clone(),
+        title: posts[*index].metadata.title.clone(),
+      })
+      .collect();
+
+    for (position, index) in members.iter().enumerate() {
+      let post = &mut posts[*index];
+      post.series_nav = Some(SeriesNav {
+        name: post.metadata.series.clone().unwrap_or_default(),
+        slug: slug.clone(),
+        position: position + 1,
+        parts: parts.clone(),
+      });
+    }
+  }
+}
+
+/// Fill in [`Post::backlinks`] from the links between posts, following redirects from old URLs.
+///
+/// Unlisted posts aren't shown as backlinks, so they stay out of sight.
+#[cfg(feature = "ssr")]
+fn link_backlinks(posts: &mut [Post], redirects: &std::collections::HashMap<String, String>) {
+  use std::collections::{BTreeSet, HashMap};
+
+  // Slug -> posts linking to it, in the order of `posts`
+  let mut backlinks: HashMap<String, Vec<Backlink>> = HashMap::new();
+  for post in posts.iter().filter(|post| !post.metadata.unlisted) {
+    let targets: BTreeSet<&str> = post
+      .links
+      .iter()
+      .filter(|link| !link.embedded)
+      .filter_map(|link| {
+        let path = link.url.split(['#', '?']).next()?.trim_end_matches('/');
+        let path = redirects.get(path).map_or(path, String::as_str);
+        path
+          .strip_prefix("/posts/")
+          .filter(|slug| !slug.contains('/') && *slug != post.slug)
+      })
+      .collect();
+    for slug in targets {
+      backlinks.entry(slug.to_string()).or_default().push(Backlink {
+        slug: post.slug.clone(),
+        title: post.metadata.title.clone(),
+      });
+    }
+  }
+
+  for post in posts.iter_mut() {
+    post.backlinks = backlinks.remove(&post.slug).unwrap_or_default();
+  }
+}
+
+/// Remove translations, series parts and backlinks that point at posts outside `visible`.
+///
+/// Posts are linked once when they're loaded, drafts and scheduled posts included, so each
+/// request hides the ones that can't be opened yet.
+#[cfg(feature = "ssr")]
+fn drop_hidden_links(post: &mut Post, visible: &std::collections::HashSet<&str>) {
+  post
+    .translations
+    .retain(|translation| visible.contains(translation.slug.as_str()));
+  post
+    .backlinks
+    .retain(|backlink| visible.contains(backlink.slug.as_str()));
+  if let Some(series) = &mut post.series_nav {
+    series.parts.retain(|part| visible.contains(part.slug.as_str()));
+    series.position = series
+      .parts
+      .iter()
+      .position(|part| part.slug == post.slug)
+      .map_or(series.position, |index| index + 1);
+  }
+}
+
+/// Keep one version of each article for listings: the one in `lang`, falling back to the
+/// first available version when the article has not been translated into `lang`
+pub fn prefer_language(posts: Vec<PostSummary>, lang: &str) -> Vec<PostSummary> {
+  use std::collections::HashSet;
+
+  let translated: HashSet<String> = posts
+    .iter()
+    .filter(|post| post.metadata.lang == lang)
+    .map(|post| post.metadata.translation_key.clone())
+    .collect();
+  let mut seen = HashSet::new();
+
+  posts
+    .into_iter()
+    .filter(|post| {
+      let key = &post.metadata.translation_key;
+      if translated.contains(key) {
+        post.metadata.lang == lang
+      } else {
+        seen.insert(key.clone())
+      }
     })
+    .collect()
+}
+
+/// Whether a URL is relative to the page, e.g. `diagram.png` or `./files/data.csv`
+fn is_relative_url(url: &str) -> bool {
+  let scheme = url
+    .split_once(':')
+    .is_some_and(|(scheme, _)| !scheme.contains('/'));
+  !(url.is_empty() || url.starts_with(['/', '#', '?']) || scheme)
+}
+
+/// Resolve a page-relative URL against a bundle's asset base (`/posts/<slug>/`)
+fn resolve_relative_url(url: &str, base: Option<&str>) -> String {
+  match base {
+    Some(base) if is_relative_url(url) => {
+      format!("{}{}", base, url.trim_start_matches("./"))
+    }
+    _ => url.to_string(),
+  }
+}
+
+/// Rewrite relative `src`, `href` and `poster` attributes in raw HTML
+fn resolve_relative_html(html: &str, base: &str) -> String {
+  let mut output = String::with_capacity(html.len());
+  let mut rest = html;
+
+  while let Some(start) = ["src=\"", "href=\"", "poster=\""]
+    .iter()
+    .filter_map(|attribute| rest.find(attribute).map(|index| index + attribute.len()))
+    .min()
+  {
+    let Some(end) = rest[start..].find('"') else {
+      break;
+    };
+    output.push_str(&rest[..start]);
+    output.push_str(&resolve_relative_url(&rest[start..start + end], Some(base)));
+    rest = &rest[start + end..];
+  }
+  output.push_str(rest);
+  output
+}
+
+/// Where a page bundle's files live and the URL they're served under (`/posts/<slug>/`)
+pub(crate) struct BundleAssets<'a> {
+  pub url: &'a str,
+  #[cfg_attr(not(feature = "ssr"), allow(dead_code))]
+  pub dir: &'a Path,
+}
+
+/// A `/`-separated path that can't escape the directory it's joined to
+#[cfg(feature = "ssr")]
+pub(crate) fn is_safe_relative_path(file: &str) -> bool {
+  file
+    .split('/')
+    .all(|segment| !segment.is_empty() && segment != "." && segment != ".." && !segment.contains('\\'))
+}
+
+/// Point relative image and link URLs of a page bundle at the bundle's files
+fn resolve_bundle_urls<'a>(
+  event: pulldown_cmark::Event<'a>,
+  base: Option<&str>,
+) -> pulldown_cmark::Event<'a> {
+  use pulldown_cmark::{Event, Tag};
+
+  let Some(base) = base else {
+    return event;
+  };
+  match event {
+    Event::Start(Tag::Image {
+      link_type,
+      dest_url,
+      title,
+      id,
+    }) => Event::Start(Tag::Image {
+      link_type,
+      dest_url: resolve_relative_url(&dest_url, Some(base)).into(),
+      title,
+      id,
+    }),
+    Event::Start(Tag::Link {
+      link_type,
+      dest_url,
+      title,
+      id,
+    }) => Event::Start(Tag::Link {
+      link_type,
+      dest_url: resolve_relative_url(&dest_url, Some(base)).into(),
+      title,
+      id,
+    }),
+    Event::Html(html) => Event::Html(resolve_relative_html(&html, base).into()),
+    Event::InlineHtml(html) => Event::InlineHtml(resolve_relative_html(&html, base).into()),
+    event => event,
+  }
+}
+
+/// Prefix of footnote ids; besides headings, footnotes are the only elements whose `id`
+/// the sanitizer keeps, and only with this prefix
+pub(crate) const FOOTNOTE_ID_PREFIX: &str = "fn-";
+
+/// Give footnote definitions, and the references that link to them, prefixed ids
+fn prefix_footnote_ids(event: pulldown_cmark::Event) -> pulldown_cmark::Event {
+  use pulldown_cmark::{Event, Tag};
+
+  let prefixed = |label: &str| format!("{}{}", FOOTNOTE_ID_PREFIX, label).into();
+  match event {
+    Event::FootnoteReference(label) => Event::FootnoteReference(prefixed(&label)),
+    Event::Start(Tag::FootnoteDefinition(label)) => {
+      Event::Start(Tag::FootnoteDefinition(prefixed(&label)))
+    }
+    event => event,
   }
 }
 
-fn markdown_to_html(markdown: &str) -> String {
-  use pulldown_cmark::{html, Options, Parser};
+/// Markdown extensions enabled for post content
+fn markdown_options() -> pulldown_cmark::Options {
+  use pulldown_cmark::Options;
 
   let mut options = Options::empty();
   options.insert(Options::ENABLE_STRIKETHROUGH);
@@ -167,66 +1207,328 @@ fn markdown_to_html(markdown: &str) -> String {
   options.insert(Options::ENABLE_TASKLISTS);
   options.insert(Options::ENABLE_HEADING_ATTRIBUTES);
   options.insert(Options::ENABLE_SMART_PUNCTUATION);
-  options.insert(Options::ENABLE_PLUSES_DELIMITED_METADATA_BLOCKS);
-  options.insert(Options::ENABLE_YAML_STYLE_METADATA_BLOCKS);
+  options.insert(Options::ENABLE_GFM);
+  options.insert(Options::ENABLE_MATH);
+  options
+}
+
+/// Remember the first image of the content, collecting its alt text from the events inside it
+#[derive(Default)]
+struct FirstImage {
+  image: Option<(String, String)>,
+  in_alt: bool,
+}
+
+fn capture_first_image<'a>(
+  event: pulldown_cmark::Event<'a>,
+  first: &mut FirstImage,
+) -> pulldown_cmark::Event<'a> {
+  use pulldown_cmark::{Event, Tag, TagEnd};
+
+  match &event {
+    Event::Start(Tag::Image { dest_url, .. }) if first.image.is_none() => {
+      first.image = Some((dest_url.to_string(), String::new()));
+      first.in_alt = true;
+    }
+    Event::End(TagEnd::Image) => first.in_alt = false,
+    Event::Text(text) | Event::Code(text) if first.in_alt => {
+      if let Some((_, alt)) = &mut first.image {
+        alt.push_str(text);
+      }
+    }
+    Event::Html(html) | Event::InlineHtml(html) if first.image.is_none() => {
+      if let Some(tag) = html.find("<img").map(|start| &html[start..]) {
+        let tag = &tag[..tag.find('>').unwrap_or(tag.len())];
+        if let Some(src) = html_attribute(tag, "src") {
+          first.image = Some((src, html_attribute(tag, "alt").unwrap_or_default()));
+        }
+      }
+    }
+    _ => {}
+  }
+  event
+}
+
+/// Value of a double-quoted attribute in an HTML tag
+fn html_attribute(tag: &str, name: &str) -> Option<String> {
+  let pattern = format!(" {}=\"", name);
+  let start = tag.find(&pattern)? + pattern.len();
+  let end = tag[start..].find('"')?;
+  Some(tag[start..start + end].to_string())
+}
+
+/// Render GitHub-style `> [!NOTE]` blockquotes as titled callouts.
+///
+/// `admonitions` tracks open blockquotes so nested plain quotes close correctly.
+fn render_admonition<'a>(
+  event: pulldown_cmark::Event<'a>,
+  admonitions: &mut Vec<bool>,
+) -> pulldown_cmark::Event<'a> {
+  use pulldown_cmark::{BlockQuoteKind, Event, Tag, TagEnd};
+
+  match event {
+    Event::Start(Tag::BlockQuote(Some(kind))) => {
+      admonitions.push(true);
+      let (class, title, icon) = match kind {
+        BlockQuoteKind::Note => (
+          "note",
+          "Note",
+          r#"<circle cx="12" cy="12" r="10"></circle><line x1="12" y1="16" x2="12" y2="12"></line><line x1="12" y1="8" x2="12.01" y2="8"></line>"#,
+        ),
+        BlockQuoteKind::Tip => (
+          "tip",
+          "Tip",
+          r#"<polygon points="13 2 3 14 12 14 11 22 21 10 12 10 13 2"></polygon>"#,
+        ),
+        BlockQuoteKind::Important => (
+          "important",
+          "Important",
+          r#"<path d="M21 15a2 2 0 0 1-2 2H7l-4 4V5a2 2 0 0 1 2-2h14a2 2 0 0 1 2 2z"></path><line x1="12" y1="7" x2="12" y2="10"></line><line x1="12" y1="13" x2="12.01" y2="13"></line>"#,
+        ),
+        BlockQuoteKind::Warning => (
+          "warning",
+          "Warning",
+          r#"<path d="M10.29 3.86L1.82 18a2 2 0 0 0 1.71 3h16.94a2 2 0 0 0 1.71-3L13.71 3.86a2 2 0 0 0-3.42 0z"></path><line x1="12" y1="9" x2="12" y2="13"></line><line x1="12" y1="17" x2="12.01" y2="17"></line>"#,
+        ),
+        BlockQuoteKind::Caution => (
+          "caution",
+          "Caution",
+          r#"<polygon points="7.86 2 16.14 2 22 7.86 22 16.14 16.14 22 7.86 22 2 16.14 2 7.86 7.86 2"></polygon><line x1="12" y1="8" x2="12" y2="12"></line><line x1="12" y1="16" x2="12.01" y2="16"></line>"#,
+        ),
+      };
+      Event::Html(
+        format!(
+          r#"<aside class="admonition admonition-{}" role="note" aria-label="{}"><p class="admonition-title"><svg class="admonition-icon" xmlns="http://www.w3.org/2000/svg" width="16" height="16" viewBox="0 0 24 24" fill="none" stroke="currentColor" stroke-width="2" stroke-linecap="round" stroke-linejoin="round" aria-hidden="true">{}</svg>{}</p>"#,
+          class, title, icon, title
+        )
+        .into(),
+      )
+    }
+    Event::Start(Tag::BlockQuote(None)) => {
+      admonitions.push(false);
+      event
+    }
+    Event::End(TagEnd::BlockQuote) => match admonitions.pop() {
+      Some(true) => Event::Html("</aside>\n".into()),
+      _ => event,
+    },
+    event => event,
+  }
+}
+
+/// A TeX formula that failed to parse, by line in the rendered markdown
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct MathError {
+  pub line: usize,
+  pub message: String,
+}
+
+/// Report TeX errors at their line in the file
+pub(crate) fn invalid_math(path: &Path, body: &Body, errors: Vec<MathError>) -> Vec<PostError> {
+  errors
+    .into_iter()
+    .map(|error| PostError::InvalidMath {
+      path: path.to_path_buf(),
+      line: body.file_line(error.line),
+      message: error.message,
+    })
+    .collect()
+}
+
+/// Render `$...$` and `$$...$$` as MathML, falling back to the TeX source when it doesn't parse
+#[cfg(feature = "ssr")]
+fn render_math<'a>(
+  event: pulldown_cmark::Event<'a>,
+  markdown: &str,
+  offset: usize,
+  errors: &mut Vec<MathError>,
+) -> pulldown_cmark::Event<'a> {
+  use pulldown_cmark::Event;
+
+  let (tex, display) = match &event {
+    Event::InlineMath(tex) => (tex, false),
+    Event::DisplayMath(tex) => (tex, true),
+    _ => return event,
+  };
+
+  let html = match crate::math::to_mathml(tex, display) {
+    Ok(mathml) if display => format!(r#"<span class="math-display">{}</span>"#, mathml),
+    Ok(mathml) => mathml,
+    Err(message) => {
+      let delimiter = if display { "$$" } else { "$" };
+      let html = format!(
+        r#"<code class="math-error" title="{}">{}{}{}</code>"#,
+        escape_html(&message),
+        delimiter,
+        escape_html(tex),
+        delimiter
+      );
+      errors.push(MathError {
+        line: markdown[..offset].matches('\n').count() + 1,
+        message,
+      });
+      html
+    }
+  };
+  Event::InlineHtml(html.into())
+}
+
+/// A URL in a link, image or HTML attribute, by line in the rendered markdown
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct LinkReference {
+  pub url: String,
+  pub line: usize,
+  /// An image or other embedded file (`src`, `poster`) rather than a link
+  pub embedded: bool,
+}
+
+/// Record the link and image URLs of an event, including `href`, `src` and `poster` in raw HTML
+#[cfg(feature = "ssr")]
+fn collect_links(
+  event: &pulldown_cmark::Event,
+  markdown: &str,
+  offset: usize,
+  links: &mut Vec<LinkReference>,
+) {
+  use pulldown_cmark::{Event, Tag};
+
+  let line = || markdown[..offset].matches('\n').count() + 1;
+  match event {
+    Event::Start(Tag::Link { dest_url, .. }) => links.push(LinkReference {
+      url: dest_url.to_string(),
+      line: line(),
+      embedded: false,
+    }),
+    Event::Start(Tag::Image { dest_url, .. }) => links.push(LinkReference {
+      url: dest_url.to_string(),
+      line: line(),
+      embedded: true,
+    }),
+    Event::Html(html) | Event::InlineHtml(html) => {
+      for attribute in [" href=\"", " src=\"", " poster=\""] {
+        for (index, _) in html.match_indices(attribute) {
+          let start = index + attribute.len();
+          let Some(end) = html[start..].find('"') else {
+            continue;
+          };
+          links.push(LinkReference {
+            url: html[start..start + end].to_string(),
+            line: line() + html[..index].matches('\n').count(),
+            embedded: attribute != " href=\"",
+          });
+        }
+      }
+    }
+    _ => {}
+  }
+}
+
+/// HTML for a markdown body along with what was collected while rendering it
+pub(crate) struct RenderedMarkdown {
+  pub html: String,
+  pub toc: Vec<TocEntry>,
+  pub math_errors: Vec<MathError>,
+  /// URL and alt text of the first image, markdown or `<img>`
+  pub first_image: Option<(String, String)>,
+  /// Every link and image URL, markdown or HTML, for the link checker
+  pub links: Vec<LinkReference>,
+}
+
+pub(crate) fn markdown_to_html(
+  markdown: &str,
+  bundle: Option<&BundleAssets>,
+  wiki: &WikiIndex,
+) -> RenderedMarkdown {
+  use pulldown_cmark::{html, Parser};
+
+  let options = markdown_options();
+  let asset_base = bundle.map(|bundle| bundle.url);
+
+  // `[[slug]]` links become markdown links; the link checker reports the unresolved ones
+  let (markdown, unresolved) = crate::wiki_links::expand(markdown, wiki);
+  let markdown = markdown.as_str();
+  #[cfg_attr(not(feature = "ssr"), allow(unused_mut))]
+  let mut links: Vec<LinkReference> = unresolved
+    .into_iter()
+    .map(|link| LinkReference {
+      url: format!("[[{}]]", link.target),
+      line: link.line,
+      embedded: false,
+    })
+    .collect();
 
   #[cfg(feature = "ssr")]
   {
+    use crate::highlight::{render_code_block, FenceInfo};
     use pulldown_cmark::{CodeBlockKind, Event, Tag, TagEnd};
-    use syntect::highlighting::ThemeSet;
-    use syntect::html::highlighted_html_for_string;
-    use syntect::parsing::SyntaxSet;
 
-    let parser = Parser::new_ext(markdown, options);
-    let ss = SyntaxSet::load_defaults_newlines();
-    let ts = ThemeSet::load_defaults();
-    // Use a neutral theme that works in both light and dark modes // base16-ocean.dark, base16-ocean.light, InspiredGitHub, Solarized (dark), Solarized (light)
-    let _theme = &ts.themes["Solarized (dark)"];
+    let mut math_errors = Vec::new();
+    let mut admonitions = Vec::new();
+    let mut first_image = FirstImage::default();
+    let parser = Parser::new_ext(markdown, options)
+      .into_offset_iter()
+      .map(|(event, range)| {
+        let event = render_math(event, markdown, range.start, &mut math_errors);
+        let event = resolve_bundle_urls(event, asset_base);
+        let event = prefix_footnote_ids(event);
+        collect_links(&event, markdown, range.start, &mut links);
+        event
+      })
+      .map(|event| capture_first_image(event, &mut first_image))
+      .map(|event| render_admonition(event, &mut admonitions));
 
     let mut in_code_block = false;
-    let mut code_block_lang = String::new();
+    let mut code_block_info = FenceInfo::default();
     let mut code_block_content = String::new();
+    // Local images are replaced by `<picture>` once their alt text has been collected
+    let mut image: Option<(ResponsiveImage, String)> = None;
+    let mut image_alt = String::new();
 
     let events: Vec<Event> = parser
       .filter_map(|event| match event {
-        Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(lang))) => {
+        Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(info))) => {
           in_code_block = true;
-          code_block_lang = lang.to_string();
+          code_block_info = FenceInfo::parse(&info);
           code_block_content.clear();
           None
         }
         Event::End(TagEnd::CodeBlock) if in_code_block => {
           in_code_block = false;
-          let syntax = ss
-            .find_syntax_by_token(&code_block_lang)
-            .unwrap_or_else(|| ss.find_syntax_plain_text());
-
-          // Generate dark theme version // base16-ocean.dark, base16-ocean.light, InspiredGitHub, Solarized (dark), Solarized (light)
-          let dark_theme = &ts.themes["Solarized (dark)"];
-          let mut dark_html = highlighted_html_for_string(&code_block_content, &ss, syntax, dark_theme)
-            .unwrap_or_else(|_| format!("<pre><code>{}</code></pre>", code_block_content));
-          dark_html = dark_html.replace(
-            r#"<pre style="background-color:"#,
-            r#"<pre class="code-dark" style="padding:1rem;border-radius:8px;overflow-x:auto;background-color:"#,
-          );
-
-          // Generate light theme version // base16-ocean.dark, base16-ocean.light, InspiredGitHub, Solarized (dark), Solarized (light)
-          let light_theme = &ts.themes["Solarized (light)"];
-          let mut light_html = highlighted_html_for_string(&code_block_content, &ss, syntax, light_theme)
-            .unwrap_or_else(|_| format!("<pre><code>{}</code></pre>", code_block_content));
-          light_html = light_html.replace(
-            r#"<pre style="background-color:"#,
-            r#"<pre class="code-light" style="padding:1rem;border-radius:8px;overflow-x:auto;background-color:"#,
-          );
-          
-          // Combine both versions
-          let combined_html = format!("{}{}", dark_html, light_html);
-          Some(Event::Html(combined_html.into()))
+          Some(Event::Html(
+            render_code_block(&code_block_content, &code_block_info).into(),
+          ))
         }
         Event::Text(text) if in_code_block => {
           code_block_content.push_str(&text);
           None
         }
+        Event::End(TagEnd::Image) if image.is_some() => {
+          let (responsive, title) = image.take()?;
+          Some(Event::Html(responsive.to_html(&image_alt, &title).into()))
+        }
+        Event::Text(text) | Event::Code(text) if image.is_some() => {
+          image_alt.push_str(&text);
+          None
+        }
+        _ if image.is_some() => None,
+        Event::Start(Tag::Image {
+          link_type,
+          dest_url,
+          title,
+          id,
+        }) => match images::responsive_image(&dest_url, bundle) {
+          Some(responsive) => {
+            image = Some((responsive, title.to_string()));
+            image_alt.clear();
+            None
+          }
+          None => Some(Event::Start(Tag::Image {
+            link_type,
+            dest_url,
+            title,
+            id,
+          })),
+        },
         // Add target="_blank" and rel attributes to external links
         Event::Start(Tag::Link {
           link_type,
@@ -237,11 +1539,11 @@ fn markdown_to_html(markdown: &str) -> String {
           if dest_url.starts_with("http://") || dest_url.starts_with("https://") {
             let modified_html = format!(
               r#"<a href="{}" target="_blank" rel="noopener noreferrer"{}>"#,
-              dest_url,
+              escape_html(&dest_url),
               if title.is_empty() {
                 String::new()
               } else {
-                format!(r#" title="{}""#, title)
+                format!(r#" title="{}""#, escape_html(&title))
               }
             );
             Some(Event::Html(modified_html.into()))
@@ -258,84 +1560,386 @@ fn markdown_to_html(markdown: &str) -> String {
         _ => Some(event),
       })
       .collect();
+    let (events, toc) = add_heading_anchors(events);
 
     let mut html_output = String::new();
     html::push_html(&mut html_output, events.into_iter());
-    html_output
+    RenderedMarkdown {
+      html: crate::sanitize::sanitize_html(&html_output),
+      toc,
+      math_errors,
+      first_image: first_image.image,
+      links,
+    }
   }
 
   #[cfg(not(feature = "ssr"))]
   {
-    let parser = Parser::new_ext(markdown, options);
+    let mut admonitions = Vec::new();
+    let mut first_image = FirstImage::default();
+    let parser = Parser::new_ext(markdown, options)
+      .map(|event| resolve_bundle_urls(event, asset_base))
+      .map(prefix_footnote_ids)
+      .map(|event| capture_first_image(event, &mut first_image))
+      .map(|event| render_admonition(event, &mut admonitions));
+    let (events, toc) = add_heading_anchors(parser);
     let mut html_output = String::new();
-    html::push_html(&mut html_output, parser);
-    html_output
+    html::push_html(&mut html_output, events.into_iter());
+    RenderedMarkdown {
+      html: html_output,
+      toc,
+      math_errors: Vec::new(),
+      first_image: first_image.image,
+      links,
+    }
   }
 }
 
+/// Posts loaded from disk together with the files that failed to load
 #[cfg(feature = "ssr")]
-pub fn load_posts() -> Vec<Post> {
-  use std::fs;
-  use std::path::Path;
-  use std::sync::{LazyLock, Mutex};
+#[derive(Default)]
+struct LoadedPosts {
+  posts: Vec<Post>,
+  errors: Vec<PostError>,
+  /// Problems in posts that loaded anyway, such as formulas that aren't valid TeX
+  warnings: Vec<PostError>,
+  /// Old URL path -> current post URL path
+  redirects: std::collections::HashMap<String, String>,
+  /// Post titles for resolving `[[wiki links]]`, in posts and in pages
+  wiki: std::sync::Arc<WikiIndex>,
+  /// When the next scheduled post goes live. Wiki links and backlinks are resolved when posts
+  /// are read, so the posts are read again then.
+  next_publish: Option<DateTime<Utc>>,
+}
+
+#[cfg(feature = "ssr")]
+fn load_posts_cached() -> std::sync::Arc<LoadedPosts> {
+  use std::sync::{Arc, LazyLock, Mutex};
 
-  static POSTS_CACHE: LazyLock<Mutex<Option<Vec<Post>>>> = LazyLock::new(|| Mutex::new(None));
+  static POSTS_CACHE: LazyLock<Mutex<Option<Arc<LoadedPosts>>>> =
+    LazyLock::new(|| Mutex::new(None));
 
-  // Check if posts are already cached
+  // Check if posts are already cached, and still current
   if let Ok(cache) = POSTS_CACHE.lock() {
-    if let Some(ref cached_posts) = *cache {
-      return cached_posts.clone();
+    if let Some(ref cached) = *cache {
+      if cached.next_publish.is_none_or(|publish_at| publish_at > Utc::now()) {
+        return cached.clone();
+      }
     }
   }
 
-  let posts_dir = Path::new("posts");
+  let loaded = Arc::new(read_posts_dir(Path::new("posts")));
+
+  // Cache the posts
+  if let Ok(mut cache) = POSTS_CACHE.lock() {
+    *cache = Some(loaded.clone());
+  }
+
+  loaded
+}
+
+#[cfg(feature = "ssr")]
+fn read_posts_dir(posts_dir: &Path) -> LoadedPosts {
+  use std::collections::HashMap;
+  use std::fs;
+
+  let mut loaded = LoadedPosts::default();
 
   if !posts_dir.exists() {
     eprintln!("Posts directory does not exist");
-    return vec![];
+    return loaded;
   }
 
-  let mut posts = Vec::new();
+  // Read in a stable order so slug collisions are always blamed on the same file
+  let mut paths: Vec<PathBuf> = fs::read_dir(posts_dir)
+    .map(|entries| entries.flatten().map(|entry| entry.path()).collect())
+    .unwrap_or_default();
+  paths.sort();
 
-  if let Ok(entries) = fs::read_dir(posts_dir) {
-    for entry in entries.flatten() {
-      let path = entry.path();
+  // URL path -> file that claimed it first
+  let mut claimed: HashMap<String, PathBuf> = HashMap::new();
+  let authors = crate::authors::load_authors();
 
-      if path.extension().and_then(|s| s.to_str()) == Some("md") {
-        if let Ok(content) = fs::read_to_string(&path) {
-          let filename = path
-            .file_stem()
-            .and_then(|s| s.to_str())
-            .unwrap_or("")
-            .to_string();
+  let mut files = Vec::new();
+  for entry in paths {
+    // `posts/<slug>.md`, or a `posts/<slug>/index.md` bundle with co-located assets
+    let (path, is_bundle) = if entry.is_dir() {
+      let index = entry.join("index.md");
+      if !index.is_file() {
+        continue;
+      }
+      (index, true)
+    } else if entry.extension().and_then(|s| s.to_str()) == Some("md") {
+      (entry.clone(), false)
+    } else {
+      continue;
+    };
 
-          match Post::from_markdown(filename, &content) {
-            Ok(post) => posts.push(post),
-            Err(e) => eprintln!("Error parsing post {}: {}", path.display(), e),
-          }
+    let filename = if is_bundle {
+      entry.file_name()
+    } else {
+      entry.file_stem()
+    }
+    .and_then(|s| s.to_str())
+    .unwrap_or("")
+    .to_string();
+
+    let content = fs::read_to_string(&path).map_err(|e| PostError::Io {
+      path: path.clone(),
+      message: e.to_string(),
+    });
+    files.push((path, is_bundle, filename, content));
+  }
+
+  // Wiki links are titled with the post they point at, so look up every title first
+  let now = Utc::now();
+  let show_drafts = crate::config::site_config().show_drafts;
+  loaded.wiki = std::sync::Arc::new(build_wiki_index(&files, |metadata| {
+    show_drafts || metadata.is_published_at(now)
+  }));
+
+  for (path, is_bundle, filename, content) in files {
+    let result = content
+      .and_then(|content| {
+        if is_bundle {
+          Post::from_bundle(&path, filename, &content, &loaded.wiki)
+        } else {
+          Post::from_markdown(&path, filename, &content, &loaded.wiki)
+        }
+      })
+      .and_then(|post| post.resolve_authors(&path, &authors))
+      .and_then(|post| {
+        let url = format!("/posts/{}", post.slug);
+        let mut urls = std::iter::once(&url).chain(post.redirect_from());
+        match urls.find_map(|url| claimed.get(url).map(|other| (url, other))) {
+          Some((url, other)) => Err(PostError::DuplicateSlug {
+            path: path.clone(),
+            url: url.clone(),
+            other: other.clone(),
+          }),
+          None => Ok(post),
+        }
+      });
+
+    match result {
+      Ok(mut post) => {
+        loaded.warnings.append(&mut post.warnings);
+        let url = format!("/posts/{}", post.slug);
+        for old_url in post.redirect_from() {
+          claimed.insert(old_url.clone(), path.clone());
+          loaded.redirects.insert(old_url.clone(), url.clone());
         }
+        claimed.insert(url, path.clone());
+        loaded.posts.push(post);
+      }
+      Err(e) => {
+        eprintln!("Error loading post: {}", e);
+        loaded.errors.push(e);
       }
     }
   }
 
-  posts.sort_by(|a, b| b.metadata.date.cmp(&a.metadata.date));
+  loaded
+    .posts
+    .sort_by_key(|post| std::cmp::Reverse(post.metadata.date));
+  loaded.errors.sort_by(|a, b| a.path().cmp(b.path()));
+  loaded.warnings.sort_by(|a, b| a.path().cmp(b.path()));
 
-  // Cache the posts
-  if let Ok(mut cache) = POSTS_CACHE.lock() {
-    *cache = Some(posts.clone());
+  if !show_drafts {
+    loaded.next_publish = next_publish(&loaded.posts, now);
+  }
+  link_translations(&mut loaded.posts);
+  link_series(&mut loaded.posts);
+  link_backlinks(&mut loaded.posts, &loaded.redirects);
+  let og_card = crate::og_image::cards_available();
+  for post in &mut loaded.posts {
+    post.og_card = og_card;
+  }
+
+  loaded
+}
+
+/// Every name a `[[wiki link]]` may use for a `visible` post: its slug, its filename and its
+/// aliases.
+///
+/// Only the front matter is read, since no post can be rendered before this is known. Files
+/// that fail to parse are left out and reported when they're loaded. Links to hidden posts
+/// render like links to missing ones, so drafts don't leak their titles; posts are read again
+/// when a scheduled one goes live (see [`load_posts_cached`]).
+#[cfg(feature = "ssr")]
+fn build_wiki_index(
+  files: &[(PathBuf, bool, String, Result<String, PostError>)],
+  visible: impl Fn(&PostMetadata) -> bool,
+) -> WikiIndex {
+  let mut index = WikiIndex::default();
+  let mut old_names = Vec::new();
+
+  for (_, _, filename, content) in files {
+    let metadata = content.as_ref().ok().and_then(|content| {
+      let front_matter = front_matter::split(content).ok()?;
+      front_matter::parse::<PostMetadata>(&front_matter).ok()
+    });
+    let Some(metadata) = metadata.filter(|metadata| visible(metadata)) else {
+      continue;
+    };
+    let slug = metadata
+      .slug
+      .as_deref()
+      .map(str::trim)
+      .filter(|slug| is_valid_slug(slug))
+      .unwrap_or(filename);
+
+    index.insert(slug, slug, &metadata.title);
+    let aliases = metadata
+      .aliases
+      .iter()
+      .filter_map(|alias| alias_url(alias)?.strip_prefix("/posts/").map(str::to_string));
+    for name in std::iter::once(filename.clone()).chain(aliases) {
+      old_names.push((name, slug.to_string(), metadata.title.clone()));
+    }
   }
 
+  // A post's current slug wins over another post's old name
+  for (name, slug, title) in old_names {
+    index.insert(&name, &slug, &title);
+  }
+  index
+}
+
+/// The earliest publish time of a post scheduled after `now`
+#[cfg(feature = "ssr")]
+fn next_publish(posts: &[Post], now: DateTime<Utc>) -> Option<DateTime<Utc>> {
   posts
+    .iter()
+    .filter_map(|post| post.metadata.publish_at)
+    .map(|publish_at| publish_at.with_timezone(&Utc))
+    .filter(|publish_at| *publish_at > now)
+    .min()
 }
 
+/// Whether a post may be served at `now`, honoring preview mode
 #[cfg(feature = "ssr")]
-pub fn load_post_summaries() -> Vec<PostSummary> {
+fn is_visible(post: &Post, now: DateTime<Utc>) -> bool {
+  crate::config::site_config().show_drafts || post.metadata.is_published_at(now)
+}
+
+/// Posts that can be opened by URL right now, including unlisted ones.
+///
+/// Scheduled posts are filtered on every call, so they appear without a restart.
+#[cfg(feature = "ssr")]
+pub fn load_posts() -> Vec<Post> {
+  use std::collections::HashSet;
+
+  let now = Utc::now();
+  let loaded = load_posts_cached();
+  let visible: Vec<&Post> = loaded
+    .posts
+    .iter()
+    .filter(|post| is_visible(post, now))
+    .collect();
+  let slugs: HashSet<&str> = visible.iter().map(|post| post.slug.as_str()).collect();
+  visible
+    .into_iter()
+    .map(|post| {
+      let mut post = post.clone();
+      drop_hidden_links(&mut post, &slugs);
+      post
+    })
+    .collect()
+}
+
+/// Posts that belong in listings, feeds and the sitemap (visible and not unlisted)
+#[cfg(feature = "ssr")]
+pub fn load_listed_posts() -> Vec<Post> {
   load_posts()
+    .into_iter()
+    .filter(|post| !post.metadata.unlisted)
+    .collect()
+}
+
+/// Post titles by slug, filename and alias, for resolving `[[wiki links]]` outside posts
+#[cfg(feature = "ssr")]
+pub(crate) fn load_wiki_index() -> std::sync::Arc<WikiIndex> {
+  load_posts_cached().wiki.clone()
+}
+
+/// Posts that failed to load, sorted by file path
+#[cfg(feature = "ssr")]
+pub fn load_post_errors() -> Vec<PostError> {
+  load_posts_cached().errors.clone()
+}
+
+/// Problems in posts that still loaded, sorted by file path, for `check` and the startup log
+#[cfg(feature = "ssr")]
+pub fn load_post_warnings() -> Vec<PostError> {
+  load_posts_cached().warnings.clone()
+}
+
+/// File inside a visible post's page bundle, for serving `/posts/<slug>/<file>`.
+///
+/// Only plain relative paths below the bundle directory are accepted, and the post's own
+/// markdown is never served.
+#[cfg(feature = "ssr")]
+pub fn find_post_asset(slug: &str, file: &str) -> Option<PathBuf> {
+  let now = Utc::now();
+  let loaded = load_posts_cached();
+  let bundle_dir = loaded
+    .posts
+    .iter()
+    .find(|post| post.slug == slug && is_visible(post, now))?
+    .bundle_dir
+    .as_ref()?;
+
+  let safe = is_safe_relative_path(file);
+  let is_markdown = Path::new(file).extension().is_some_and(|ext| ext == "md");
+  if !safe || is_markdown {
+    return None;
+  }
+
+  let path = bundle_dir.join(file);
+  path.is_file().then_some(path)
+}
+
+/// Where an old post URL (filename slug or alias) now lives, for 301 redirects to visible posts
+#[cfg(feature = "ssr")]
+pub fn find_redirect(path: &str) -> Option<String> {
+  let path = match path.trim_end_matches('/') {
+    "" => "/",
+    trimmed => trimmed,
+  };
+  let now = Utc::now();
+  visible_redirect(&load_posts_cached(), path, |post| is_visible(post, now))
+}
+
+/// Redirect target for `path`, unless the post it points at is hidden.
+///
+/// An alias of a draft or scheduled post must not reveal the post's slug, so it 404s like the
+/// post itself.
+#[cfg(feature = "ssr")]
+fn visible_redirect(
+  loaded: &LoadedPosts,
+  path: &str,
+  visible: impl Fn(&Post) -> bool,
+) -> Option<String> {
+  let target = loaded.redirects.get(path)?;
+  let slug = target.strip_prefix("/posts/")?;
+  loaded
+    .posts
+    .iter()
+    .any(|post| post.slug == slug && visible(post))
+    .then(|| target.clone())
+}
+
+#[cfg(feature = "ssr")]
+pub fn load_post_summaries() -> Vec<PostSummary> {
+  load_listed_posts()
     .into_iter()
     .map(|post| PostSummary {
       slug: post.slug,
       metadata: post.metadata,
       thumbnail: post.thumbnail,
+      reading_time: post.reading_time,
+      authors: post.authors,
     })
     .collect()
 }
@@ -344,3 +1948,223 @@ pub fn load_post_summaries() -> Vec<PostSummary> {
 pub fn load_post_summaries() -> Vec<PostSummary> {
   vec![]
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn valid_slugs() {
+    for slug in ["my-post", "part_2", "v1.2", "2025-01-15-Welcome-en", "한글-제목"] {
+      assert!(is_valid_slug(slug), "{} should be valid", slug);
+    }
+  }
+
+  #[test]
+  fn invalid_slugs() {
+    for slug in ["", "a/b", "a b", "a?b", "a#b", "50%"] {
+      assert!(!is_valid_slug(slug), "{} should be invalid", slug);
+    }
+  }
+
+  /// A post file with the required fields added to `front_matter`
+  #[cfg(feature = "ssr")]
+  fn source(front_matter: &str) -> String {
+    format!("---\n{}\ndescription: d\ntags: []\n---\n\nBody.\n", front_matter)
+  }
+
+  #[cfg(feature = "ssr")]
+  fn post(slug: &str, front_matter: &str) -> Post {
+    let path = PathBuf::from(format!("posts/{}.md", slug));
+    Post::from_markdown(&path, slug.to_string(), &source(front_matter), &WikiIndex::default())
+      .unwrap()
+  }
+
+  #[cfg(feature = "ssr")]
+  #[test]
+  fn redirects_skip_hidden_posts() {
+    let now = Utc::now();
+    let mut loaded = LoadedPosts {
+      posts: vec![
+        post("draft", "title: Draft\ndate: 2025-01-01\ndraft: true"),
+        post("scheduled", "title: Later\ndate: 2025-01-01\npublish_at: 2999-01-01"),
+        post("live", "title: Live\ndate: 2025-01-01"),
+      ],
+      ..LoadedPosts::default()
+    };
+    for slug in ["draft", "scheduled", "live"] {
+      loaded
+        .redirects
+        .insert(format!("/posts/old-{}", slug), format!("/posts/{}", slug));
+    }
+    let published = |post: &Post| post.metadata.is_published_at(now);
+
+    assert_eq!(visible_redirect(&loaded, "/posts/old-draft", published), None);
+    assert_eq!(visible_redirect(&loaded, "/posts/old-scheduled", published), None);
+    assert_eq!(
+      visible_redirect(&loaded, "/posts/old-live", published),
+      Some("/posts/live".to_string())
+    );
+    assert_eq!(
+      visible_redirect(&loaded, "/posts/old-draft", |_| true),
+      Some("/posts/draft".to_string())
+    );
+    assert_eq!(visible_redirect(&loaded, "/posts/unknown", |_| true), None);
+  }
+
+  #[cfg(feature = "ssr")]
+  #[test]
+  fn wiki_links_to_hidden_posts_render_as_missing() {
+    let file = |filename: &str, front_matter: &str| {
+      (
+        PathBuf::from(format!("posts/{}.md", filename)),
+        false,
+        filename.to_string(),
+        Ok(source(front_matter)),
+      )
+    };
+    let files = vec![
+      file(
+        "2025-01-01-Secret-en",
+        "title: Secret Plans\ndate: 2025-01-01\ndraft: true\nslug: secret\naliases: [old-secret]",
+      ),
+      file("2025-01-02-Public-en", "title: Public Post\ndate: 2025-01-02\nslug: public"),
+    ];
+    let now = Utc::now();
+    let index = build_wiki_index(&files, |metadata| metadata.is_published_at(now));
+
+    let markdown = "See [[secret]], [[old-secret|this]] and [[public#Getting Started]].\n";
+    let (expanded, unresolved) = crate::wiki_links::expand(markdown, &index);
+    assert!(!expanded.contains("Secret Plans"), "{}", expanded);
+    assert!(!expanded.contains("/posts/secret"), "{}", expanded);
+    let missing = r#"<span class="wiki-link-missing" title="No post named secret">secret</span>"#;
+    assert!(expanded.contains(missing), "{}", expanded);
+    assert!(
+      expanded.contains(r#"[Public Post](</posts/public#getting-started> "Public Post")"#),
+      "{}",
+      expanded
+    );
+    let targets: Vec<&str> = unresolved.iter().map(|link| link.target.as_str()).collect();
+    assert_eq!(targets, ["secret", "old-secret"]);
+
+    assert_eq!(
+      crate::wiki_links::plain_text(markdown, &index),
+      "See secret, this and Public Post.\n"
+    );
+
+    let preview = build_wiki_index(&files, |_| true);
+    let (expanded, unresolved) = crate::wiki_links::expand("[[secret]]", &preview);
+    assert!(expanded.contains("/posts/secret"), "{}", expanded);
+    assert!(unresolved.is_empty());
+  }
+
+  #[cfg(feature = "ssr")]
+  #[test]
+  fn next_publish_is_the_earliest_future_publish_at() {
+    let now = "2025-06-01T00:00:00Z".parse::<DateTime<Utc>>().unwrap();
+    let posts = vec![
+      post("past", "title: Past\ndate: 2025-01-01\npublish_at: 2025-05-01T00:00:00Z"),
+      post("later", "title: Later\ndate: 2025-01-01\npublish_at: 2025-09-01T00:00:00Z"),
+      post("sooner", "title: Sooner\ndate: 2025-01-01\npublish_at: 2025-07-01T09:00:00+09:00"),
+      post("live", "title: Live\ndate: 2025-01-01"),
+    ];
+
+    let expected = "2025-07-01T00:00:00Z".parse::<DateTime<Utc>>().unwrap();
+    assert_eq!(next_publish(&posts, now), Some(expected));
+    assert_eq!(next_publish(&posts[..1], now), None);
+  }
+
+  mod reading_time {
+    use super::*;
+
+    #[test]
+    fn counts_words() {
+      let markdown = "# Hello world\n\nIt's a *well-known* `fact`.\n";
+      let reading_time = ReadingTime::from_markdown(markdown);
+      assert_eq!(reading_time.words, 6);
+      assert_eq!(reading_time.minutes, 1);
+      assert_eq!(ReadingTime::from_markdown("").minutes, 1);
+    }
+
+    #[test]
+    fn counts_cjk_characters_as_words() {
+      assert_eq!(ReadingTime::from_markdown("안녕하세요 세계").words, 7);
+      assert_eq!(ReadingTime::from_markdown("日本語のテキスト").words, 8);
+      assert_eq!(ReadingTime::from_markdown("Rust로 만든 blog").words, 5);
+    }
+
+    #[test]
+    fn skips_code_blocks() {
+      let markdown = "One two\n\n```rust\nfn main() {}\n```\n\n    indented code\n";
+      assert_eq!(ReadingTime::from_markdown(markdown).words, 2);
+    }
+
+    #[test]
+    fn rounds_minutes_up() {
+      let words = |count: usize| vec!["word"; count].join(" ");
+      assert_eq!(ReadingTime::from_markdown(&words(230)).minutes, 1);
+      assert_eq!(ReadingTime::from_markdown(&words(231)).minutes, 2);
+      assert_eq!(ReadingTime::from_markdown(&"한".repeat(500)).minutes, 1);
+      assert_eq!(ReadingTime::from_markdown(&"한".repeat(501)).minutes, 2);
+      // Half a minute of each
+      let mixed = format!("{} {}", words(115), "한".repeat(250));
+      assert_eq!(ReadingTime::from_markdown(&mixed).minutes, 1);
+      assert_eq!(ReadingTime::from_markdown(&mixed).iso_duration(), "PT1M");
+    }
+  }
+
+  mod heading_anchors {
+    use super::*;
+
+    fn anchors(markdown: &str) -> (String, Vec<TocEntry>) {
+      let parser = pulldown_cmark::Parser::new_ext(markdown, markdown_options());
+      let (events, toc) = add_heading_anchors(parser);
+      let mut html = String::new();
+      pulldown_cmark::html::push_html(&mut html, events.into_iter());
+      (html, toc)
+    }
+
+    fn ids(toc: &[TocEntry]) -> Vec<String> {
+      toc
+        .iter()
+        .flat_map(|entry| std::iter::once(entry.id.clone()).chain(ids(&entry.children)))
+        .collect()
+    }
+
+    #[test]
+    fn ids_from_heading_text() {
+      assert_eq!(heading_id("Getting Started"), "getting-started");
+      assert_eq!(heading_id("  What's new in v1.2?  "), "what-s-new-in-v1-2");
+      assert_eq!(heading_id("한글 제목"), "한글-제목");
+      assert_eq!(heading_id("snake_case"), "snake_case");
+      assert_eq!(heading_id("!!!"), "section");
+    }
+
+    #[test]
+    fn duplicate_headings_get_suffixes() {
+      let (html, toc) = anchors("## Setup\n\n## Setup\n\n## Setup 1\n\n## Setup\n");
+      assert_eq!(ids(&toc), ["setup", "setup-1", "setup-1-1", "setup-2"]);
+      let second = r##"<h2 id="setup-1">Setup<a class="heading-anchor" href="#setup-1""##;
+      assert!(html.contains(second), "{}", html);
+    }
+
+    #[test]
+    fn explicit_ids_are_kept_and_reserved() {
+      let (_, toc) = anchors("## Setup\n\n## Installing {#setup-1}\n\n## Setup\n");
+      assert_eq!(ids(&toc), ["setup", "setup-1", "setup-2"]);
+      assert_eq!(toc[1].title, "Installing");
+    }
+
+    #[test]
+    fn toc_nests_by_level() {
+      let markdown = "# Title\n\n## A\n\n### A.1\n\n#### A.1.a\n\n## B\n\n### `code`\n";
+      let (_, toc) = anchors(markdown);
+      assert_eq!(toc.len(), 1);
+      assert_eq!(toc[0].count(), 6);
+      let titles: Vec<&str> = toc[0].children.iter().map(|entry| &*entry.title).collect();
+      assert_eq!(titles, ["A", "B"]);
+      assert_eq!(toc[0].children[0].children[0].children[0].id, "a-1-a");
+      assert_eq!(toc[0].children[1].children[0].title, "code");
+    }
+  }
+}
@@ -1,26 +1,85 @@
+use crate::escape::escape_html;
 use crate::posts::Post;
+use chrono::Utc;
 
 pub fn generate_rss(posts: &[Post]) -> String {
-  let mut rss = String::from(
+  // The feed changes whenever a post is published or revised
+  let last_build_date = posts
+    .iter()
+    .map(|post| post.metadata.last_modified())
+    .max()
+    .map_or_else(|| Utc::now().to_rfc2822(), |date| date.to_rfc2822());
+
+  let mut rss = format!(
     r#"<?xml version="1.0" encoding="UTF-8"?>
-<rss version="2.0" xmlns:atom="http://www.w3.org/2005/Atom">
+<rss version="2.0" xmlns:atom="http://www.w3.org/2005/Atom" xmlns:dc="http://purl.org/dc/elements/1.1/" xmlns:media="http://search.yahoo.com/mrss/">
   <channel>
     <title>Your Blog Name</title>
     <link>https://your-domain.com</link>
     <description>A blog about programming, technology, and software development</description>
     <language>en-us</language>
     <atom:link href="https://your-domain.com/rss.xml" rel="self" type="application/rss+xml"/>
-    <lastBuildDate>Sun, 13 Oct 2025 00:00:00 GMT</lastBuildDate>
+    <lastBuildDate>{}</lastBuildDate>
     <generator>Leptos RSS Generator</generator>
 "#,
+    last_build_date
   );
 
   // Add posts
   for post in posts.iter().take(20) {
     // Limit to most recent 20 posts
-    let pub_date = format_rfc2822_date(&post.metadata.date);
+    let pub_date = post.metadata.date.to_rfc2822();
     let post_url = format!("https://your-domain.com/posts/{}", post.slug);
 
+    // RSS <author> must be an email address; dc:creator carries the display name
+    let mut authors = String::new();
+    for author in &post.authors {
+      if let Some(email) = &author.email {
+        authors.push_str(&format!(
+          "      <author>{} ({})</author>\n",
+          escape_html(email),
+          escape_html(&author.name)
+        ));
+      }
+      authors.push_str(&format!(
+        "      <dc:creator>{}</dc:creator>\n",
+        escape_html(&author.name)
+      ));
+    }
+
+    // The cover (or first image) for feed readers that show one
+    let mut image = String::new();
+    if let Some(thumbnail) = &post.thumbnail {
+      let url = if thumbnail.image.src.starts_with('/') {
+        format!("https://your-domain.com{}", thumbnail.image.src)
+      } else {
+        thumbnail.image.src.clone()
+      };
+      let url = escape_html(&url);
+      let mime = image_mime_type(&thumbnail.image.src);
+      image.push_str(&format!(
+        "      <enclosure url=\"{}\" length=\"{}\" type=\"{}\"/>\n",
+        url,
+        thumbnail.image.bytes.unwrap_or(0),
+        mime
+      ));
+      let size = match (thumbnail.image.width, thumbnail.image.height) {
+        (Some(width), Some(height)) => format!(" width=\"{}\" height=\"{}\"", width, height),
+        _ => String::new(),
+      };
+      image.push_str(&format!(
+        "      <media:content url=\"{}\" medium=\"image\" type=\"{}\"{}>\n",
+        url, mime, size
+      ));
+      if !thumbnail.alt.is_empty() {
+        image.push_str(&format!(
+          "        <media:description type=\"plain\">{}</media:description>\n",
+          escape_html(&thumbnail.alt)
+        ));
+      }
+      image.push_str("      </media:content>\n");
+    }
+
     rss.push_str(&format!(
       r#"    <item>
       <title><![CDATA[{}]]></title>
@@ -29,14 +88,16 @@ pub fn generate_rss(posts: &[Post]) -> String {
       <pubDate>{}</pubDate>
       <description><![CDATA[{}]]></description>
       <category><![CDATA[{}]]></category>
-    </item>
+{}{}    </item>
 "#,
       post.metadata.title,
       post_url,
       post_url,
       pub_date,
       post.metadata.description,
-      post.metadata.tags.join(", ")
+      post.metadata.tags.join(", "),
+      authors,
+      image
     ));
   }
 
@@ -44,12 +105,16 @@ pub fn generate_rss(posts: &[Post]) -> String {
   rss
 }
 
-fn format_rfc2822_date(date_str: &str) -> String {
-  // Convert YYYY-MM-DD to RFC2822 format
-  // This is a simple conversion, in production you might want to use a proper date library
-  match date_str {
-    "2025-10-13" => "Sun, 13 Oct 2025 00:00:00 GMT".to_string(),
-    "2025-10-12" => "Sat, 12 Oct 2025 00:00:00 GMT".to_string(),
-    _ => format!("{} 00:00:00 GMT", date_str), // Fallback
+/// MIME type of an image from its file extension
+fn image_mime_type(url: &str) -> &'static str {
+  let path = url.split(['?', '#']).next().unwrap_or_default();
+  let extension = path.rsplit_once('.').map(|(_, ext)| ext.to_lowercase());
+  match extension.as_deref() {
+    Some("jpg" | "jpeg") => "image/jpeg",
+    Some("gif") => "image/gif",
+    Some("webp") => "image/webp",
+    Some("svg") => "image/svg+xml",
+    Some("avif") => "image/avif",
+    _ => "image/png",
   }
 }
@@ -0,0 +1,272 @@
+// HTML sanitization for rendered markdown, so raw HTML in a post can't run scripts
+
+use crate::posts::FOOTNOTE_ID_PREFIX;
+use crate::shortcodes::{IFRAME_SANDBOX, YOUTUBE_FACADE_STYLE};
+use ammonia::Builder;
+use std::borrow::Cow;
+use std::sync::LazyLock;
+
+/// Tags allowed on top of ammonia's defaults (which cover ordinary text, lists, tables,
+/// `<figure>`, `<details>`, `<kbd>`, ...): media, embeds, task list checkboxes, and the
+/// SVG icons and MathML the markdown pipeline produces itself
+const EXTRA_TAGS: &[&str] = &[
+  "picture", "source", "video", "audio", "track", "iframe", "input", "svg", "path", "circle",
+  "line", "polygon", "polyline", "rect", "g",
+];
+
+const MATHML_TAGS: &[&str] = &[
+  "math", "semantics", "annotation", "mrow", "mi", "mn", "mo", "mtext", "mspace", "ms", "mfrac",
+  "msqrt", "mroot", "mstyle", "msub", "msup", "msubsup", "munder", "mover", "munderover",
+  "mtable", "mtr", "mtd", "mpadded", "mphantom", "menclose",
+];
+
+const MATHML_ATTRIBUTES: &[&str] = &[
+  "display", "xmlns", "mathvariant", "displaystyle", "scriptlevel", "stretchy", "fence",
+  "separator", "form", "lspace", "rspace", "accent", "accentunder", "movablelimits",
+  "symmetric", "minsize", "maxsize", "largeop", "linethickness", "columnalign", "rowalign",
+  "columnspacing", "rowspacing", "width", "height", "depth", "encoding", "notation", "style",
+];
+
+/// Attributes allowed on any tag, besides `lang` and `title`. `id` is only kept on headings
+/// and footnotes, so a post can't clobber globals such as `window.config` with `id="config"`
+const GENERIC_ATTRIBUTES: &[&str] = &["class", "role", "dir"];
+
+/// Per-tag attributes, on top of ammonia's defaults (`a[href]`, `img[src alt ...]`, ...)
+const TAG_ATTRIBUTES: &[(&str, &[&str])] = &[
+  ("a", &["href", "hreflang", "target", "name"]),
+  (
+    "img",
+    &["src", "srcset", "sizes", "alt", "width", "height", "loading", "decoding", "fetchpriority"],
+  ),
+  ("source", &["src", "srcset", "sizes", "type", "media"]),
+  (
+    "video",
+    &["src", "poster", "controls", "autoplay", "loop", "muted", "playsinline", "preload", "width", "height"],
+  ),
+  ("audio", &["src", "controls", "autoplay", "loop", "muted", "preload"]),
+  ("track", &["src", "kind", "srclang", "label", "default"]),
+  (
+    "iframe",
+    &["src", "srcdoc", "title", "loading", "allow", "allowfullscreen", "width", "height", "referrerpolicy"],
+  ),
+  ("input", &["type", "checked", "disabled"]),
+  ("h1", &["id"]),
+  ("h2", &["id"]),
+  ("h3", &["id"]),
+  ("h4", &["id"]),
+  ("h5", &["id"]),
+  ("h6", &["id"]),
+  ("div", &["id"]),
+  ("th", &["style"]),
+  ("td", &["style"]),
+  (
+    "svg",
+    &[
+      "xmlns", "viewBox", "width", "height", "fill", "stroke", "stroke-width", "stroke-linecap",
+      "stroke-linejoin",
+    ],
+  ),
+  ("path", &["d", "fill", "fill-rule", "clip-rule", "stroke"]),
+  ("circle", &["cx", "cy", "r", "fill", "stroke"]),
+  ("line", &["x1", "y1", "x2", "y2", "stroke"]),
+  ("polygon", &["points", "fill", "stroke"]),
+  ("polyline", &["points", "fill", "stroke"]),
+  ("rect", &["x", "y", "width", "height", "rx", "ry", "fill", "stroke"]),
+  ("g", &["fill", "stroke", "transform"]),
+];
+
+/// CSS properties kept in `style` attributes: table alignment and MathML text styling
+const STYLE_PROPERTIES: &[&str] = &["text-align", "font-style", "font-weight", "border", "padding"];
+
+static SANITIZER: LazyLock<Builder<'static>> = LazyLock::new(|| {
+  let mut builder = Builder::default();
+  builder
+    .add_tags(EXTRA_TAGS)
+    .add_tags(MATHML_TAGS)
+    .add_generic_attributes(GENERIC_ATTRIBUTES)
+    .add_generic_attribute_prefixes(["data-", "aria-"])
+    .filter_style_properties(STYLE_PROPERTIES.iter().copied().collect())
+    // Every link gets ammonia's `rel="noopener noreferrer"`, so `target="_blank"` is safe
+    .set_tag_attribute_value("iframe", "sandbox", IFRAME_SANDBOX)
+    .attribute_filter(filter_attribute);
+  for (tag, attributes) in TAG_ATTRIBUTES {
+    builder.add_tag_attributes(*tag, *attributes);
+  }
+  for tag in MATHML_TAGS {
+    builder.add_tag_attributes(*tag, MATHML_ATTRIBUTES);
+  }
+  builder
+});
+
+/// The YouTube shortcode's click-to-load facade: a thumbnail linking to the player
+static SRCDOC_SANITIZER: LazyLock<Builder<'static>> = LazyLock::new(|| {
+  let mut builder = Builder::empty();
+  builder
+    .tags(["a", "img", "span"].into_iter().collect())
+    .clean_content_tags(["script", "style"].into_iter().collect())
+    .tag_attributes(
+      [
+        ("a", ["href"].into_iter().collect()),
+        ("img", ["src", "alt", "loading"].into_iter().collect()),
+      ]
+      .into_iter()
+      .collect(),
+    )
+    .url_schemes(["https"].into_iter().collect())
+    .url_relative(ammonia::UrlRelative::Deny)
+    .attribute_filter(|element, attribute, value| match (element, attribute) {
+      ("a", "href") if !is_allowed_iframe_src(value) => None,
+      _ => Some(value.into()),
+    });
+  builder
+});
+
+fn filter_attribute<'u>(element: &str, attribute: &str, value: &'u str) -> Option<Cow<'u, str>> {
+  match (element, attribute) {
+    ("iframe", "src") if !is_allowed_iframe_src(value) => None,
+    ("iframe", "srcdoc") => Some(clean_srcdoc(value).into()),
+    ("input", "type") if value != "checkbox" => None,
+    ("a", "target") if value != "_blank" => None,
+    ("div", "id") if !value.starts_with(FOOTNOTE_ID_PREFIX) => None,
+    _ => Some(value.into()),
+  }
+}
+
+/// Only the facade's own stylesheet is kept, so a `srcdoc` can't restyle anything else
+fn clean_srcdoc(srcdoc: &str) -> String {
+  match srcdoc.strip_prefix(YOUTUBE_FACADE_STYLE) {
+    Some(rest) => format!("{}{}", YOUTUBE_FACADE_STYLE, SRCDOC_SANITIZER.clean(rest)),
+    None => SRCDOC_SANITIZER.clean(srcdoc).to_string(),
+  }
+}
+
+/// Embeds may only load from `https://` hosts in `BLOG_IFRAME_HOSTS`
+fn is_allowed_iframe_src(src: &str) -> bool {
+  let Some(rest) = src.strip_prefix("https://") else {
+    return false;
+  };
+  let host = rest.split(['/', '?', '#']).next().unwrap_or_default();
+  crate::config::site_config()
+    .iframe_hosts
+    .iter()
+    .any(|allowed| allowed.eq_ignore_ascii_case(host))
+}
+
+/// Strip scripts, event handlers, `javascript:` URLs and anything else outside the allowlists
+///
+/// Off when `BLOG_SANITIZE_HTML=false`, for sites where every author is trusted.
+pub fn sanitize_html(html: &str) -> String {
+  if !crate::config::site_config().sanitize_html {
+    return html.to_string();
+  }
+  SANITIZER.clean(html).to_string()
+}
+
+// These rely on the default configuration: sanitizing on and the default iframe hosts
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn strips_scripts_handlers_and_javascript_urls() {
+    let html = sanitize_html(
+      r#"<p onclick="alert(1)">Hi<script>alert(1)</script></p><a href="javascript:alert(1)">x</a>"#,
+    );
+    assert!(html.contains("<p>Hi</p>"), "{}", html);
+    assert!(!html.contains("script"), "{}", html);
+    assert!(!html.contains("onclick"), "{}", html);
+    assert!(!html.contains("javascript:"), "{}", html);
+  }
+
+  #[test]
+  fn keeps_markdown_output() {
+    let html = r#"<h2 id="setup">Setup</h2><p><a href="/posts/next" title="Next" rel="noopener noreferrer">next</a> <code>x</code></p>"#;
+    assert_eq!(sanitize_html(html), html);
+  }
+
+  #[test]
+  fn links_opening_a_new_tab_get_noopener() {
+    let html = sanitize_html(r#"<a href="https://example.com/" target="_blank" rel="opener">x</a>"#);
+    assert_eq!(
+      html,
+      r#"<a href="https://example.com/" target="_blank" rel="noopener noreferrer">x</a>"#
+    );
+  }
+
+  #[test]
+  fn keeps_iframes_from_allowed_hosts() {
+    let html =
+      sanitize_html(r#"<iframe src="https://player.vimeo.com/video/1" allowfullscreen></iframe>"#);
+    assert!(html.contains(r#"src="https://player.vimeo.com/video/1""#), "{}", html);
+  }
+
+  #[test]
+  fn drops_iframe_src_from_other_hosts() {
+    for src in [
+      "https://evil.example/embed",
+      "http://player.vimeo.com/video/1",
+      "https://player.vimeo.com.evil.example/video/1",
+      "//player.vimeo.com/video/1",
+    ] {
+      let html = sanitize_html(&format!(r#"<iframe src="{}"></iframe>"#, src));
+      assert!(!html.contains("src="), "{} was kept: {}", src, html);
+    }
+  }
+
+  #[test]
+  fn keeps_the_youtube_facade() {
+    let expanded =
+      crate::shortcodes::expand("{{< youtube dQw4w9WgXcQ >}}", std::path::Path::new("test.md"))
+        .unwrap();
+    let html = sanitize_html(&expanded.text);
+    assert!(html.contains("srcdoc=\"&lt;style&gt;*{margin:0"), "{}", html);
+    assert!(html.contains(&format!("sandbox=\"{}\"", IFRAME_SANDBOX)), "{}", html);
+    for url in [
+      "https://i.ytimg.com/vi/dQw4w9WgXcQ/hqdefault.jpg",
+      "https://www.youtube-nocookie.com/embed/dQw4w9WgXcQ?autoplay=1",
+    ] {
+      assert!(html.contains(url), "{} is missing: {}", url, html);
+    }
+  }
+
+  #[test]
+  fn cleans_srcdoc() {
+    let html = sanitize_html(
+      r#"<iframe srcdoc="&lt;script&gt;alert(1)&lt;/script&gt;&lt;a href=&quot;https://evil.example/&quot;&gt;x&lt;/a&gt;&lt;img src=&quot;x&quot; onerror=&quot;alert(1)&quot;&gt;"></iframe>"#,
+    );
+    assert!(html.contains("srcdoc="), "{}", html);
+    assert!(!html.contains("&lt;script"), "{}", html);
+    assert!(!html.contains("alert"), "{}", html);
+    assert!(!html.contains("evil.example"), "{}", html);
+    assert!(!html.contains("onerror"), "{}", html);
+  }
+
+  #[test]
+  fn drops_other_styles_from_srcdoc() {
+    let html = sanitize_html(
+      r#"<iframe srcdoc="&lt;style&gt;body{background:url(https://evil.example/)}&lt;/style&gt;&lt;span&gt;x&lt;/span&gt;"></iframe>"#,
+    );
+    assert!(html.contains("srcdoc=\"&lt;span&gt;x&lt;/span&gt;\""), "{}", html);
+    assert!(!html.contains("evil.example"), "{}", html);
+  }
+
+  #[test]
+  fn sandboxes_iframes() {
+    let html = sanitize_html(
+      r#"<iframe src="https://player.vimeo.com/video/1" sandbox="allow-top-navigation"></iframe>"#,
+    );
+    assert!(html.contains(&format!(r#"sandbox="{}""#, IFRAME_SANDBOX)), "{}", html);
+    assert!(!html.contains("allow-top-navigation"), "{}", html);
+  }
+
+  #[test]
+  fn keeps_ids_only_on_headings_and_footnotes() {
+    let html = sanitize_html(
+      r#"<h3 id="setup">Setup</h3><div id="fn-1">Note</div><div id="config">x</div><img id="x" src="/a.png" alt="">"#,
+    );
+    assert_eq!(
+      html,
+      r#"<h3 id="setup">Setup</h3><div id="fn-1">Note</div><div>x</div><img src="/a.png" alt="">"#
+    );
+  }
+}
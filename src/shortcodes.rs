@@ -0,0 +1,776 @@
+// Shortcodes: `{{< name arg key="value" >}}` embeds expanded inside markdown content
+//
+// A shortcode may also wrap content, `{{< name >}}inner{{< /name >}}`. Shortcodes inside
+// code spans and fenced code blocks are left alone, and `{{</* name */>}}` renders the
+// shortcode literally for documentation.
+//
+// Site owners can add their own handlers before the content is first loaded:
+//
+// ```ignore
+// use blog_starter_rs::escape::escape_html;
+//
+// blog_starter_rs::shortcodes::register("kbd", |shortcode| {
+//   let keys = shortcode.require("keys")?;
+//   Ok(format!("<kbd>{}</kbd>", escape_html(keys)))
+// });
+// ```
+
+use crate::escape::escape_html;
+use crate::front_matter::FrontMatter;
+use crate::posts::{LinkReference, PostError};
+use std::collections::HashMap;
+use std::path::{Component, Path};
+use std::sync::{Arc, LazyLock, RwLock};
+
+/// A shortcode invocation passed to its handler
+#[derive(Debug)]
+pub struct Shortcode<'a> {
+  pub name: &'a str,
+  /// Arguments given without a key, in order
+  pub positional: Vec<String>,
+  /// `key="value"` arguments
+  pub named: HashMap<String, String>,
+  /// Content between the opening and closing tags of a paired shortcode
+  pub inner: Option<&'a str>,
+  /// Markdown file being rendered; file arguments resolve against its directory
+  pub source: &'a Path,
+}
+
+impl Shortcode<'_> {
+  /// A named argument
+  pub fn get(&self, key: &str) -> Option<&str> {
+    self.named.get(key).map(String::as_str)
+  }
+
+  /// A named argument, falling back to the positional argument at `position`
+  pub fn arg(&self, key: &str, position: usize) -> Option<&str> {
+    self
+      .get(key)
+      .or_else(|| self.positional.get(position).map(String::as_str))
+  }
+
+  /// A named argument that must be present
+  pub fn require(&self, key: &str) -> Result<&str, String> {
+    self
+      .get(key)
+      .ok_or_else(|| format!("`{}` requires a `{}` argument", self.name, key))
+  }
+}
+
+/// Renders a shortcode to markdown (raw HTML is fine) or explains why it can't
+pub type ShortcodeHandler = Arc<dyn Fn(&Shortcode) -> Result<String, String> + Send + Sync>;
+
+/// A shortcode that could not be expanded
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShortcodeError {
+  /// 1-based line within the expanded text
+  pub line: usize,
+  pub message: String,
+}
+
+static REGISTRY: LazyLock<RwLock<HashMap<String, ShortcodeHandler>>> = LazyLock::new(|| {
+  let mut registry: HashMap<String, ShortcodeHandler> = HashMap::new();
+  registry.insert("youtube".to_string(), Arc::new(youtube));
+  registry.insert("figure".to_string(), Arc::new(figure));
+  registry.insert("include".to_string(), Arc::new(include));
+  registry.insert("audio".to_string(), Arc::new(audio));
+  registry.insert("video".to_string(), Arc::new(video));
+  RwLock::new(registry)
+});
+
+/// Register a shortcode handler, replacing any built-in with the same name
+pub fn register<F>(name: &str, handler: F)
+where
+  F: Fn(&Shortcode) -> Result<String, String> + Send + Sync + 'static,
+{
+  if let Ok(mut registry) = REGISTRY.write() {
+    registry.insert(name.to_string(), Arc::new(handler));
+  }
+}
+
+fn handler(name: &str) -> Option<ShortcodeHandler> {
+  REGISTRY.read().ok()?.get(name).cloned()
+}
+
+/// Names of all registered shortcodes, sorted
+pub fn registered() -> Vec<String> {
+  let mut names: Vec<String> = REGISTRY
+    .read()
+    .map(|registry| registry.keys().cloned().collect())
+    .unwrap_or_default();
+  names.sort();
+  names
+}
+
+const OPEN: &str = "{{<";
+const CLOSE: &str = ">}}";
+
+/// Markdown with its shortcodes expanded
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Expanded {
+  pub text: String,
+  /// Line of the original markdown each line of `text` came from, 1-based; every line of a
+  /// shortcode's output maps to the line the shortcode ends on
+  pub lines: Vec<usize>,
+}
+
+/// Tracks which line of the original markdown each line of the output came from
+struct LineMap {
+  lines: Vec<usize>,
+  line: usize,
+  consumed: usize,
+  output_len: usize,
+}
+
+impl LineMap {
+  fn new() -> Self {
+    LineMap {
+      lines: vec![1],
+      line: 1,
+      consumed: 0,
+      output_len: 0,
+    }
+  }
+
+  /// Map the lines `output` gained since the last call to where `rest` now starts
+  fn sync(&mut self, markdown: &str, rest: &str, output: &str) {
+    let consumed = markdown.len() - rest.len();
+    self.line += markdown[self.consumed..consumed].matches('\n').count();
+    let added = output[self.output_len..].matches('\n').count();
+    self.lines.extend(std::iter::repeat_n(self.line, added));
+    self.consumed = consumed;
+    self.output_len = output.len();
+  }
+}
+
+/// Expand every shortcode in `markdown`, which was read from `source`
+pub fn expand(markdown: &str, source: &Path) -> Result<Expanded, ShortcodeError> {
+  let mut output = String::with_capacity(markdown.len());
+  let mut rest = markdown;
+  let mut fence: Option<String> = None;
+  let mut line_map = LineMap::new();
+
+  while !rest.is_empty() {
+    line_map.sync(markdown, rest, &output);
+    let line_end = rest.find('\n').map_or(rest.len(), |index| index + 1);
+    let line = &rest[..line_end];
+
+    // Fenced code blocks are copied verbatim
+    if let Some(marker) = &fence {
+      if is_closing_fence(line, marker) {
+        fence = None;
+      }
+      output.push_str(line);
+      rest = &rest[line_end..];
+      continue;
+    }
+    if let Some(marker) = fence_marker(line) {
+      fence = Some(marker);
+      output.push_str(line);
+      rest = &rest[line_end..];
+      continue;
+    }
+
+    let Some(start) = find_outside_code(line, OPEN) else {
+      output.push_str(line);
+      rest = &rest[line_end..];
+      continue;
+    };
+
+    output.push_str(&line[..start]);
+    let consumed = markdown.len() - rest.len() + start;
+    let line_number = markdown[..consumed].matches('\n').count() + 1;
+    let error = |message: String| ShortcodeError {
+      line: line_number,
+      message,
+    };
+
+    let after_open = &rest[start + OPEN.len()..];
+    let Some(close) = after_open.find(CLOSE) else {
+      return Err(error("shortcode is never closed with `>}}`".to_string()));
+    };
+    let tag = &after_open[..close];
+    let mut remaining = &after_open[close + CLOSE.len()..];
+
+    // `{{</* name */>}}` is written out as a literal shortcode
+    if let Some(literal) = tag
+      .trim()
+      .strip_prefix("/*")
+      .and_then(|tag| tag.strip_suffix("*/"))
+    {
+      output.push_str(&format!("{{{{< {} >}}}}", literal.trim()));
+      rest = remaining;
+      continue;
+    }
+
+    let (name, positional, named) = parse_tag(tag).map_err(error)?;
+    if name.starts_with('/') {
+      return Err(error(format!(
+        "`{}` has no matching opening shortcode",
+        name
+      )));
+    }
+    let Some(handler) = handler(&name) else {
+      return Err(error(format!(
+        "unknown shortcode `{}` (available: {})",
+        name,
+        registered().join(", ")
+      )));
+    };
+
+    // A matching closing tag makes this a paired shortcode
+    let inner = match find_closing(remaining, &name) {
+      Some((inner_end, closing_len)) => {
+        let inner = &remaining[..inner_end];
+        remaining = &remaining[inner_end + closing_len..];
+        Some(inner.trim_matches('\n'))
+      }
+      None => None,
+    };
+
+    let shortcode = Shortcode {
+      name: &name,
+      positional,
+      named,
+      inner,
+      source,
+    };
+    let rendered = handler(&shortcode).map_err(error)?;
+    output.push_str(&rendered);
+
+    // On a line of its own the output is an HTML block, which only a blank line ends
+    let rest_of_line = remaining.split('\n').next().unwrap_or("");
+    if line[..start].trim().is_empty()
+      && rest_of_line.trim().is_empty()
+      && !rendered.ends_with("\n\n")
+    {
+      output.push('\n');
+    }
+    rest = remaining;
+  }
+  line_map.sync(markdown, rest, &output);
+
+  Ok(Expanded {
+    text: output,
+    lines: line_map.lines,
+  })
+}
+
+/// Expand the shortcodes in a post or page body, reporting errors at their line in the file
+pub(crate) fn expand_body(path: &Path, front_matter: &FrontMatter) -> Result<Body, PostError> {
+  let expanded = expand(front_matter.body, path).map_err(|error| PostError::InvalidShortcode {
+    path: path.to_path_buf(),
+    line: front_matter.body_line + error.line - 1,
+    message: error.message,
+  })?;
+
+  let leading = leading_lines(&expanded.text);
+  Ok(Body {
+    markdown: expanded.text.trim().to_string(),
+    lines: expanded.lines[leading..]
+      .iter()
+      .map(|line| front_matter.body_line + line - 1)
+      .collect(),
+  })
+}
+
+/// A post or page body ready to render: shortcodes expanded and surrounding whitespace trimmed
+pub(crate) struct Body {
+  pub markdown: String,
+  /// Line in the file of each line of `markdown`
+  lines: Vec<usize>,
+}
+
+impl Body {
+  /// Line in the file of 1-based `line` of the body
+  pub fn file_line(&self, line: usize) -> usize {
+    self
+      .lines
+      .get(line.saturating_sub(1))
+      .or(self.lines.last())
+      .copied()
+      .unwrap_or(1)
+  }
+
+  /// Move link references from lines of the body to lines of the file
+  pub fn link_lines(&self, links: Vec<LinkReference>) -> Vec<LinkReference> {
+    links
+      .into_iter()
+      .map(|link| LinkReference {
+        line: self.file_line(link.line),
+        ..link
+      })
+      .collect()
+  }
+}
+
+/// Number of lines `trim()` drops from the start of a markdown body
+fn leading_lines(body: &str) -> usize {
+  body[..body.len() - body.trim_start().len()]
+    .matches('\n')
+    .count()
+}
+
+/// Opening fence (```` ``` ```` or `~~~`, possibly longer) of a fenced code block
+pub(crate) fn fence_marker(line: &str) -> Option<String> {
+  let trimmed = line.trim_start();
+  ['`', '~'].iter().find_map(|&c| {
+    let count = trimmed.chars().take_while(|&x| x == c).count();
+    (count >= 3).then(|| c.to_string().repeat(count))
+  })
+}
+
+/// A fence of the same character, at least as long as the opening one, and nothing else
+pub(crate) fn is_closing_fence(line: &str, marker: &str) -> bool {
+  let trimmed = line.trim();
+  let Some(c) = marker.chars().next() else {
+    return false;
+  };
+  trimmed.len() >= marker.len() && trimmed.chars().all(|x| x == c)
+}
+
+/// Byte offset of `needle` in `line`, skipping inline code spans
+pub(crate) fn find_outside_code(line: &str, needle: &str) -> Option<usize> {
+  let mut index = 0;
+  while index < line.len() {
+    let rest = &line[index..];
+    if rest.starts_with(needle) {
+      return Some(index);
+    }
+    if rest.starts_with('`') {
+      let ticks = rest.chars().take_while(|&c| c == '`').count();
+      let delimiter = "`".repeat(ticks);
+      match rest[ticks..].find(&delimiter) {
+        Some(end) => {
+          index += ticks + end + ticks;
+          continue;
+        }
+        None => return None,
+      }
+    }
+    index += rest.chars().next().map_or(1, char::len_utf8);
+  }
+  None
+}
+
+/// Offset and length of `{{< /name >}}` in `text`, allowing any spacing inside the braces
+fn find_closing(text: &str, name: &str) -> Option<(usize, usize)> {
+  let mut offset = 0;
+  while let Some(start) = text[offset..].find(OPEN) {
+    let start = offset + start;
+    let after = &text[start + OPEN.len()..];
+    let end = after.find(CLOSE)?;
+    if after[..end].trim().strip_prefix('/').map(str::trim) == Some(name) {
+      return Some((start, OPEN.len() + end + CLOSE.len()));
+    }
+    offset = start + OPEN.len();
+  }
+  None
+}
+
+type ParsedTag = (String, Vec<String>, HashMap<String, String>);
+
+/// Split `name arg key="value" key2='v' key3=v` into its parts
+fn parse_tag(tag: &str) -> Result<ParsedTag, String> {
+  let mut tokens = Vec::new();
+  let mut chars = tag.trim().chars().peekable();
+
+  while let Some(&c) = chars.peek() {
+    if c.is_whitespace() {
+      chars.next();
+      continue;
+    }
+    let mut token = String::new();
+    let mut quoted_value = false;
+    while let Some(&c) = chars.peek() {
+      if c.is_whitespace() {
+        break;
+      }
+      chars.next();
+      if c == '"' || c == '\'' {
+        quoted_value = true;
+        let mut closed = false;
+        while let Some(inner) = chars.next() {
+          match inner {
+            '\\' => token.extend(chars.next()),
+            _ if inner == c => {
+              closed = true;
+              break;
+            }
+            _ => token.push(inner),
+          }
+        }
+        if !closed {
+          return Err(format!("unterminated quote in shortcode `{}`", tag.trim()));
+        }
+      } else {
+        token.push(c);
+      }
+    }
+    tokens.push((token, quoted_value));
+  }
+
+  let mut tokens = tokens.into_iter();
+  let name = match tokens.next() {
+    Some((name, false)) if !name.is_empty() => name,
+    _ => return Err("shortcode is missing a name".to_string()),
+  };
+
+  let mut positional = Vec::new();
+  let mut named = HashMap::new();
+  for (token, _) in tokens {
+    match token.split_once('=') {
+      Some((key, value)) if !key.is_empty() => {
+        named.insert(key.to_string(), value.to_string());
+      }
+      _ => positional.push(token),
+    }
+  }
+  Ok((name, positional, named))
+}
+
+/// Stylesheet of the YouTube facade, the only `<style>` the sanitizer keeps in a `srcdoc`
+pub(crate) const YOUTUBE_FACADE_STYLE: &str =
+  "<style>*{margin:0;padding:0}html,body{height:100%;background:#000}\
+   a,img{display:block;width:100%;height:100%;object-fit:cover}\
+   span{position:absolute;top:50%;left:50%;transform:translate(-50%,-50%);\
+   width:68px;height:48px;border-radius:12px;background:#f00;color:#fff;\
+   font:24px/48px sans-serif;text-align:center}</style>";
+
+/// `sandbox` for embeds: players may run scripts and open links, but not navigate the page
+pub(crate) const IFRAME_SANDBOX: &str =
+  "allow-scripts allow-same-origin allow-popups allow-presentation";
+
+/// YouTube video behind a click-to-load facade: the player loads from the no-cookie domain
+/// only when the reader presses play. The thumbnail still comes from `i.ytimg.com` on every
+/// view, so this keeps YouTube's player and cookies off the page, not every request
+fn youtube(shortcode: &Shortcode) -> Result<String, String> {
+  let id = shortcode
+    .arg("id", 0)
+    .ok_or("`youtube` requires an `id` argument")?;
+  let valid = !id.is_empty()
+    && id
+      .chars()
+      .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+  if !valid {
+    return Err(format!("`{}` is not a YouTube video id", id));
+  }
+  let title = shortcode.get("title").unwrap_or("YouTube video");
+  let start = match shortcode.get("start") {
+    Some(start) if start.parse::<u32>().is_ok() => format!("&start={}", start),
+    Some(start) => {
+      return Err(format!(
+        "`start` must be a number of seconds, got `{}`",
+        start
+      ))
+    }
+    None => String::new(),
+  };
+  let embed = format!(
+    "https://www.youtube-nocookie.com/embed/{}?autoplay=1{}",
+    id, start
+  );
+
+  // The facade document is shown inside the iframe until its link navigates to the player
+  let facade = format!(
+    "{}<a href=\"{}\"><img src=\"https://i.ytimg.com/vi/{}/hqdefault.jpg\" alt=\"{}\" loading=\"lazy\">\
+     <span>&#9654;</span></a>",
+    YOUTUBE_FACADE_STYLE,
+    embed,
+    id,
+    escape_html(title)
+  );
+
+  Ok(format!(
+    "<div class=\"shortcode-youtube\"><iframe srcdoc=\"{}\" src=\"{}\" title=\"{}\" loading=\"lazy\" \
+     sandbox=\"{}\" \
+     allow=\"accelerometer; autoplay; clipboard-write; encrypted-media; gyroscope; picture-in-picture\" \
+     allowfullscreen></iframe></div>",
+    escape_html(&facade),
+    escape_html(&embed),
+    escape_html(title),
+    IFRAME_SANDBOX
+  ))
+}
+
+/// Image with an optional caption and link
+fn figure(shortcode: &Shortcode) -> Result<String, String> {
+  let src = shortcode.require("src")?;
+  let alt = shortcode
+    .get("alt")
+    .or(shortcode.get("caption"))
+    .unwrap_or("");
+
+  let mut img = format!(
+    "<img src=\"{}\" alt=\"{}\" loading=\"lazy\"",
+    escape_html(src),
+    escape_html(alt)
+  );
+  for attribute in ["width", "height"] {
+    if let Some(value) = shortcode.get(attribute) {
+      img.push_str(&format!(" {}=\"{}\"", attribute, escape_html(value)));
+    }
+  }
+  img.push('>');
+  if let Some(link) = shortcode.get("link") {
+    img = format!("<a href=\"{}\">{}</a>", escape_html(link), img);
+  }
+
+  let caption = shortcode
+    .get("caption")
+    .map(|caption| format!("<figcaption>{}</figcaption>", escape_html(caption)))
+    .unwrap_or_default();
+  let class = shortcode
+    .get("class")
+    .map(|class| format!(" class=\"{}\"", escape_html(class)))
+    .unwrap_or_default();
+
+  Ok(format!("<figure{}>{}{}</figure>", class, img, caption))
+}
+
+/// Show a source file next to the markdown as a highlighted code block, gist style
+fn include(shortcode: &Shortcode) -> Result<String, String> {
+  let file = shortcode
+    .arg("file", 0)
+    .ok_or("`include` requires a `file` argument")?;
+  let relative = Path::new(file);
+  let inside = relative
+    .components()
+    .all(|component| matches!(component, Component::Normal(_)));
+  if !inside {
+    return Err(format!(
+      "`{}` must be a path below the directory of the markdown file",
+      file
+    ));
+  }
+
+  let path = shortcode
+    .source
+    .parent()
+    .unwrap_or(Path::new("."))
+    .join(relative);
+  let code = std::fs::read_to_string(&path)
+    .map_err(|e| format!("cannot include `{}`: {}", path.display(), e))?;
+
+  let lang = shortcode
+    .get("lang")
+    .or_else(|| relative.extension().and_then(|ext| ext.to_str()))
+    .unwrap_or("");
+  // Use a fence longer than any backtick run in the file
+  let longest_run = code.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+  let fence = "`".repeat(longest_run.max(2) + 1);
+  let name = relative
+    .file_name()
+    .and_then(|name| name.to_str())
+    .unwrap_or(file);
+
+  Ok(format!(
+    "<div class=\"shortcode-include\">\n<div class=\"include-file\">{}</div>\n\n{}{}\n{}\n{}\n\n</div>\n",
+    escape_html(name),
+    fence,
+    lang,
+    code.trim_end_matches('\n'),
+    fence
+  ))
+}
+
+fn audio(shortcode: &Shortcode) -> Result<String, String> {
+  let src = shortcode
+    .arg("src", 0)
+    .ok_or("`audio` requires a `src` argument")?;
+  let caption = shortcode
+    .get("caption")
+    .map(|caption| format!("<figcaption>{}</figcaption>", escape_html(caption)))
+    .unwrap_or_default();
+
+  Ok(format!(
+    "<figure class=\"shortcode-audio\"><audio controls preload=\"metadata\" src=\"{}\">\
+     <a href=\"{}\">Download audio</a></audio>{}</figure>",
+    escape_html(src),
+    escape_html(src),
+    caption
+  ))
+}
+
+fn video(shortcode: &Shortcode) -> Result<String, String> {
+  let src = shortcode
+    .arg("src", 0)
+    .ok_or("`video` requires a `src` argument")?;
+
+  let mut attributes = String::new();
+  if let Some(poster) = shortcode.get("poster") {
+    attributes.push_str(&format!(" poster=\"{}\"", escape_html(poster)));
+  }
+  for attribute in ["width", "height"] {
+    if let Some(value) = shortcode.get(attribute) {
+      attributes.push_str(&format!(" {}=\"{}\"", attribute, escape_html(value)));
+    }
+  }
+  let flag = |name: &str| {
+    shortcode.get(name) == Some("true") || shortcode.positional.iter().any(|arg| arg == name)
+  };
+  // Autoplaying video must be muted for browsers to allow it
+  if flag("autoplay") {
+    attributes.push_str(" autoplay muted playsinline");
+  } else if flag("muted") {
+    attributes.push_str(" muted");
+  }
+  if flag("loop") {
+    attributes.push_str(" loop");
+  }
+  let caption = shortcode
+    .get("caption")
+    .map(|caption| format!("<figcaption>{}</figcaption>", escape_html(caption)))
+    .unwrap_or_default();
+
+  Ok(format!(
+    "<figure class=\"shortcode-video\"><video controls preload=\"metadata\" src=\"{}\"{}>\
+     <a href=\"{}\">Download video</a></video>{}</figure>",
+    escape_html(src),
+    attributes,
+    escape_html(src),
+    caption
+  ))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn render(markdown: &str) -> String {
+    expand(markdown, Path::new("posts/test.md"))
+      .unwrap_or_else(|error| panic!("{}: {}", markdown, error.message))
+      .text
+  }
+
+  fn error(markdown: &str) -> ShortcodeError {
+    expand(markdown, Path::new("posts/test.md")).unwrap_err()
+  }
+
+  #[test]
+  fn registry() {
+    let names = registered();
+    for name in ["audio", "figure", "include", "video", "youtube"] {
+      assert!(names.iter().any(|registered| registered == name), "{:?}", names);
+    }
+
+    register("test-kbd", |shortcode| {
+      Ok(format!("<kbd>{}</kbd>", escape_html(shortcode.require("keys")?)))
+    });
+    assert_eq!(render(r#"Press {{< test-kbd keys="Ctrl+<" >}}."#), "Press <kbd>Ctrl+&lt;</kbd>.");
+    assert_eq!(
+      error("{{< test-kbd >}}").message,
+      "`test-kbd` requires a `keys` argument"
+    );
+    assert!(error("{{< nope >}}").message.starts_with("unknown shortcode `nope` (available: "));
+  }
+
+  #[test]
+  fn arguments() {
+    let (name, positional, named) =
+      parse_tag(r#" clip first title="Two words" alt='It\'s' width=640 "#).unwrap();
+    assert_eq!(name, "clip");
+    assert_eq!(positional, ["first"]);
+    assert_eq!(named["title"], "Two words");
+    assert_eq!(named["alt"], "It's");
+    assert_eq!(named["width"], "640");
+
+    assert_eq!(
+      parse_tag(r#"clip title="open"#),
+      Err(r#"unterminated quote in shortcode `clip title="open`"#.to_string())
+    );
+    assert_eq!(parse_tag("  "), Err("shortcode is missing a name".to_string()));
+  }
+
+  #[test]
+  fn closing_tags() {
+    assert_eq!(find_closing("a {{< /note >}} b", "note"), Some((2, 13)));
+    assert_eq!(find_closing("a {{</note>}}", "note"), Some((2, 11)));
+    assert_eq!(find_closing("{{< /other >}} {{< / note >}}", "note"), Some((15, 14)));
+    assert_eq!(find_closing("{{< /notes >}}", "note"), None);
+
+    register("test-upper", |shortcode| Ok(shortcode.inner.unwrap_or("").to_uppercase()));
+    assert_eq!(render("{{< test-upper >}}\nshout\n{{< /test-upper >}}\n"), "SHOUT\n\n");
+    assert_eq!(
+      error("text\n{{< /test-upper >}}").message,
+      "`/test-upper` has no matching opening shortcode"
+    );
+    assert_eq!(error("{{< figure").message, "shortcode is never closed with `>}}`");
+    assert_eq!(error("ok\n{{< figure").line, 2);
+  }
+
+  #[test]
+  fn code_and_literals_are_left_alone() {
+    let markdown = "`{{< youtube x >}}`\n```\n{{< youtube x >}}\n```\n";
+    assert_eq!(render(markdown), markdown);
+    assert_eq!(render("{{</* youtube id */>}}"), "{{< youtube id >}}");
+  }
+
+  #[test]
+  fn include_rejects_paths_outside_the_post_directory() {
+    for file in ["../../etc/passwd", "/etc/passwd", "./a/../../b", "a/../../b"] {
+      assert_eq!(
+        error(&format!("{{{{< include \"{}\" >}}}}", file)).message,
+        format!("`{}` must be a path below the directory of the markdown file", file),
+      );
+    }
+  }
+
+  #[test]
+  fn include_reads_files_next_to_the_markdown() {
+    let dir = std::env::temp_dir().join(format!("shortcodes-include-{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("src")).unwrap();
+    std::fs::write(dir.join("src/main.rs"), "fn main() {}\n// ```\n").unwrap();
+
+    let expanded = expand("{{< include src/main.rs >}}", &dir.join("index.md")).unwrap();
+    assert_eq!(
+      expanded.text,
+      "<div class=\"shortcode-include\">\n<div class=\"include-file\">main.rs</div>\n\n\
+       ````rs\nfn main() {}\n// ```\n````\n\n</div>\n\n"
+    );
+    std::fs::remove_dir_all(&dir).unwrap();
+  }
+
+  #[test]
+  fn youtube() {
+    let html = render(r#"{{< youtube dQw4w9WgXcQ start=42 title="A <b>talk</b>" >}}"#);
+    assert!(html.starts_with("<div class=\"shortcode-youtube\"><iframe srcdoc=\""), "{}", html);
+    let embed = "https://www.youtube-nocookie.com/embed/dQw4w9WgXcQ?autoplay=1&amp;start=42";
+    assert!(html.contains(&format!(r#"src="{}""#, embed)), "{}", html);
+    assert!(html.contains(r#"title="A &lt;b&gt;talk&lt;/b&gt;""#), "{}", html);
+
+    assert_eq!(
+      error(r#"{{< youtube id="x\" onload=\"alert(1)" >}}"#).message,
+      r#"`x" onload="alert(1)` is not a YouTube video id"#
+    );
+    assert_eq!(
+      error("{{< youtube abc start=soon >}}").message,
+      "`start` must be a number of seconds, got `soon`"
+    );
+  }
+
+  #[test]
+  fn figure() {
+    assert_eq!(
+      render(r#"{{< figure src="/a.png" caption="A & B" link="/big.png" width=300 >}}"#),
+      "<figure><a href=\"/big.png\"><img src=\"/a.png\" alt=\"A &amp; B\" loading=\"lazy\" \
+       width=\"300\"></a><figcaption>A &amp; B</figcaption></figure>\n"
+    );
+    assert_eq!(error("{{< figure >}}").message, "`figure` requires a `src` argument");
+  }
+
+  #[test]
+  fn audio_and_video() {
+    assert_eq!(
+      render("{{< audio /talk.mp3 >}}"),
+      "<figure class=\"shortcode-audio\"><audio controls preload=\"metadata\" src=\"/talk.mp3\">\
+       <a href=\"/talk.mp3\">Download audio</a></audio></figure>\n"
+    );
+    assert_eq!(
+      render(r#"{{< video src="/demo.mp4" poster="/demo.jpg" autoplay loop >}}"#),
+      "<figure class=\"shortcode-video\"><video controls preload=\"metadata\" src=\"/demo.mp4\" \
+       poster=\"/demo.jpg\" autoplay muted playsinline loop><a href=\"/demo.mp4\">Download video\
+       </a></video></figure>\n"
+    );
+  }
+}
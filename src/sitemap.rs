@@ -1,6 +1,7 @@
-use crate::posts::Post;
+use crate::pages::Page;
+use crate::posts::{series_slug, Post};
 
-pub fn generate_sitemap(posts: &[Post]) -> String {
+pub fn generate_sitemap(posts: &[Post], pages: &[Page]) -> String {
   let mut sitemap = String::from(
     r#"<?xml version="1.0" encoding="UTF-8"?>
 <urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
@@ -8,14 +9,27 @@ pub fn generate_sitemap(posts: &[Post]) -> String {
   );
 
   // Add homepage
-  sitemap.push_str(&format!(
+  sitemap.push_str(
     r#"  <url>
     <loc>https://your-domain.com/</loc>
     <changefreq>daily</changefreq>
     <priority>1.0</priority>
   </url>
-"#
-  ));
+"#,
+  );
+
+  // Add standalone pages
+  for page in pages {
+    sitemap.push_str(&format!(
+      r#"  <url>
+    <loc>{}</loc>
+    <changefreq>monthly</changefreq>
+    <priority>0.7</priority>
+  </url>
+"#,
+      page.url()
+    ));
+  }
 
   // Add posts
   for post in posts {
@@ -27,7 +41,8 @@ pub fn generate_sitemap(posts: &[Post]) -> String {
     <priority>0.8</priority>
   </url>
 "#,
-      post.slug, post.metadata.date
+      post.slug,
+      post.metadata.last_modified().to_rfc3339()
     ));
   }
 
@@ -51,6 +66,46 @@ pub fn generate_sitemap(posts: &[Post]) -> String {
     ));
   }
 
+  // Add series pages
+  let mut series: Vec<String> = posts
+    .iter()
+    .filter_map(|post| post.metadata.series.as_deref().map(series_slug))
+    .collect();
+  series.sort();
+  series.dedup();
+
+  for slug in series {
+    sitemap.push_str(&format!(
+      r#"  <url>
+    <loc>https://your-domain.com/series/{}</loc>
+    <changefreq>weekly</changefreq>
+    <priority>0.6</priority>
+  </url>
+"#,
+      slug
+    ));
+  }
+
+  // Add author pages
+  let mut authors: Vec<&str> = posts
+    .iter()
+    .flat_map(|post| post.authors.iter().map(|author| author.id.as_str()))
+    .collect();
+  authors.sort();
+  authors.dedup();
+
+  for id in authors {
+    sitemap.push_str(&format!(
+      r#"  <url>
+    <loc>https://your-domain.com/authors/{}</loc>
+    <changefreq>weekly</changefreq>
+    <priority>0.5</priority>
+  </url>
+"#,
+      id
+    ));
+  }
+
   sitemap.push_str("</urlset>");
   sitemap
 }
@@ -0,0 +1,163 @@
+// Wiki-style links between posts: `[[slug]]`, `[[slug|label]]` and `[[slug#heading|label]]`
+//
+// They're rewritten to ordinary markdown links before the markdown is parsed, so they get the
+// same handling and link checking as `[label](/posts/slug)`. Code spans and fenced code
+// blocks are left alone.
+
+use crate::posts::heading_id;
+use crate::escape::escape_html;
+use crate::shortcodes::{fence_marker, find_outside_code, is_closing_fence};
+use std::collections::HashMap;
+
+const OPEN: &str = "[[";
+const CLOSE: &str = "]]";
+
+/// Every name a wiki link may use for a post, with the post's current slug and title
+#[derive(Debug, Clone, Default)]
+pub struct WikiIndex {
+  posts: HashMap<String, (String, String)>,
+}
+
+impl WikiIndex {
+  /// Let `name` stand for the post at `slug`; the first post to claim a name keeps it
+  #[cfg(feature = "ssr")]
+  pub(crate) fn insert(&mut self, name: &str, slug: &str, title: &str) {
+    self
+      .posts
+      .entry(name.to_string())
+      .or_insert_with(|| (slug.to_string(), title.to_string()));
+  }
+
+  fn get(&self, name: &str) -> Option<&(String, String)> {
+    self.posts.get(name)
+  }
+}
+
+/// A wiki link to a post that doesn't exist
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct UnresolvedWikiLink {
+  /// The link target as written, e.g. `slug#heading`
+  pub target: String,
+  /// 1-based line in the markdown
+  pub line: usize,
+}
+
+/// Rewrite the wiki links in `markdown`, titled with the post they point at.
+///
+/// Links to unknown posts become a `<span class="wiki-link-missing">` with the label, and are
+/// returned so they can be reported.
+pub(crate) fn expand(markdown: &str, index: &WikiIndex) -> (String, Vec<UnresolvedWikiLink>) {
+  let mut unresolved = Vec::new();
+  let output = rewrite(markdown, |link, line| match index.get(link.name) {
+    Some((slug, title)) => {
+      // `[[post#Some Heading]]` points at the id the heading is rendered with
+      let href = match link.fragment {
+        Some(fragment) => format!("/posts/{}#{}", slug, heading_id(fragment)),
+        None => format!("/posts/{}", slug),
+      };
+      format!(
+        "[{}](<{}> \"{}\")",
+        link.label.map_or_else(|| escape_markdown(title), str::to_string),
+        href,
+        title.replace('\\', "\\\\").replace('"', "\\\"")
+      )
+    }
+    None => {
+      unresolved.push(UnresolvedWikiLink {
+        target: link.target.to_string(),
+        line,
+      });
+      format!(
+        r#"<span class="wiki-link-missing" title="No post named {}">{}</span>"#,
+        escape_html(link.name),
+        escape_html(link.label.unwrap_or(link.target))
+      )
+    }
+  });
+
+  (output, unresolved)
+}
+
+/// Replace the wiki links in `markdown` with the text they show, for plain-text previews
+pub(crate) fn plain_text(markdown: &str, index: &WikiIndex) -> String {
+  rewrite(markdown, |link, _| match (link.label, index.get(link.name)) {
+    (Some(label), _) => label.to_string(),
+    (None, Some((_, title))) => title.clone(),
+    (None, None) => link.target.to_string(),
+  })
+}
+
+/// The parts of a `[[name#fragment|label]]` link, trimmed
+struct WikiLink<'a> {
+  target: &'a str,
+  name: &'a str,
+  fragment: Option<&'a str>,
+  label: Option<&'a str>,
+}
+
+/// Replace each wiki link outside code with `replace(link, line)`, where `line` is 1-based
+fn rewrite(markdown: &str, mut replace: impl FnMut(&WikiLink, usize) -> String) -> String {
+  let mut output = String::with_capacity(markdown.len());
+  let mut fence: Option<String> = None;
+
+  for (number, line) in markdown.split_inclusive('\n').enumerate() {
+    if let Some(marker) = &fence {
+      if is_closing_fence(line, marker) {
+        fence = None;
+      }
+      output.push_str(line);
+      continue;
+    }
+    if let Some(marker) = fence_marker(line) {
+      fence = Some(marker);
+      output.push_str(line);
+      continue;
+    }
+
+    let mut rest = line;
+    while let Some(start) = find_outside_code(rest, OPEN) {
+      let Some(end) = rest[start + OPEN.len()..].find(CLOSE) else {
+        break;
+      };
+      let inner = &rest[start + OPEN.len()..start + OPEN.len() + end];
+      output.push_str(&rest[..start]);
+      rest = &rest[start + OPEN.len() + end + CLOSE.len()..];
+
+      let (target, label) = match inner.split_once('|') {
+        Some((target, label)) => (target.trim(), Some(label.trim())),
+        None => (inner.trim(), None),
+      };
+      if target.is_empty() {
+        output.push_str(&format!("{}{}{}", OPEN, inner, CLOSE));
+        continue;
+      }
+      let (name, fragment) = match target.split_once('#') {
+        Some((name, fragment)) => (name.trim(), Some(fragment.trim())),
+        None => (target, None),
+      };
+
+      let link = WikiLink {
+        target,
+        name,
+        fragment,
+        label,
+      };
+      output.push_str(&replace(&link, number + 1));
+    }
+    output.push_str(rest);
+  }
+
+  output
+}
+
+/// Backslash-escape punctuation so a post title is shown as written rather than as markdown
+fn escape_markdown(text: &str) -> String {
+  let mut escaped = String::with_capacity(text.len());
+  for c in text.chars() {
+    if c.is_ascii_punctuation() {
+      escaped.push('\\');
+    }
+    escaped.push(c);
+  }
+  escaped
+}